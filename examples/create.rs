@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::env;
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 #[serde(rename_all = "PascalCase")]
 struct Account {
     #[serde(rename = "attributes")]
@@ -13,6 +14,7 @@ struct Account {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 struct Attribute {
     url: String,
     #[serde(rename = "type")]
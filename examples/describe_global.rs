@@ -8,7 +8,7 @@ async fn main() -> Result<(), Error> {
     let username = env::var("SFDC_USERNAME").unwrap();
     let password = env::var("SFDC_PASSWORD").unwrap();
 
-    let mut client = Client::new(client_id, client_secret);
+    let mut client = Client::new(Some(client_id), Some(client_secret));
     client.login_with_credential(username, password).await?;
 
     let res = client.describe_global().await?;
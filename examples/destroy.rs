@@ -3,6 +3,7 @@ use serde::Deserialize;
 use std::env;
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 #[serde(rename_all = "PascalCase")]
 struct Account {
     #[serde(rename = "attributes")]
@@ -12,6 +13,7 @@ struct Account {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 struct Attribute {
     url: String,
     #[serde(rename = "type")]
@@ -28,8 +30,7 @@ async fn main() -> Result<(), Error> {
     let mut client = Client::new(Some(client_id), Some(client_secret));
     client.login_with_credential(username, password).await?;
 
-    let res = client.destroy("Account", "0011t00001FfDoaAAF").await?;
-    println!("{:?}", res);
+    client.destroy("Account", "0011t00001FfDoaAAF").await?;
 
     Ok(())
 }
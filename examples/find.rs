@@ -3,6 +3,7 @@ use serde::Deserialize;
 use std::env;
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 #[serde(rename_all = "PascalCase")]
 struct Account {
     #[serde(rename = "attributes")]
@@ -12,6 +13,7 @@ struct Account {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 struct Attribute {
     url: String,
     #[serde(rename = "type")]
@@ -1,3 +1,4 @@
+use rustforce::response::SearchResponse;
 use rustforce::{Client, Error};
 use std::env;
 
@@ -11,7 +12,7 @@ async fn main() -> Result<(), Error> {
     let mut client = Client::new(Some(client_id), Some(client_secret));
     client.login_with_credential(username, password).await?;
 
-    let res = client.search("FIND {rust}").await?;
+    let res: SearchResponse = client.search("FIND {rust}").await?;
     println!("{:?}", res);
 
     Ok(())
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::env;
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 #[serde(rename_all = "PascalCase")]
 struct Account {
     #[serde(rename = "attributes")]
@@ -13,6 +14,7 @@ struct Account {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 struct Attribute {
     url: String,
     #[serde(rename = "type")]
@@ -31,10 +33,9 @@ async fn main() -> Result<(), Error> {
 
     let mut params = HashMap::new();
     params.insert("Name", "hello rust");
-    let res = client
+    client
         .update("Account", "0012K00001drfGYQAY", params)
         .await?;
-    println!("{:?}", res);
 
     Ok(())
 }
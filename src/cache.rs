@@ -0,0 +1,353 @@
+//! Opt-in cache of query and describe results
+//!
+//! Results are kept for a time to live and keyed by the API version and the
+//! query text or sobject name. When the cache is full, the least recently
+//! used entry is dropped. Describe results past their time to live are
+//! revalidated with `If-Modified-Since` instead of being fetched again.
+use crate::client::Client;
+use crate::errors::Error;
+use crate::response::{DescribeGlobalResponse, QueryResponse};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+struct Entry {
+    value: serde_json::Value,
+    fetched_at: Instant,
+    last_used: Instant,
+    last_modified: Option<String>,
+}
+
+/// Client wrapper caching `query`, `describe` and `describe_global`
+pub struct CachedClient {
+    client: Arc<Client>,
+    ttl: Duration,
+    max_entries: usize,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl CachedClient {
+    /// Caches up to `max_entries` results for `ttl` each
+    pub fn new(client: Arc<Client>, ttl: Duration, max_entries: usize) -> Self {
+        CachedClient {
+            client,
+            ttl,
+            max_entries,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the wrapped client for calls that are not cached
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Query record using SOQL. Results with more batches are not cached,
+    /// as their `nextRecordsUrl` query locator expires
+    pub async fn query<T: DeserializeOwned>(&self, query: &str) -> Result<QueryResponse<T>, Error> {
+        let key = self.key("query", query);
+        if let Some((value, true, _)) = self.lookup(&key) {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        let res: QueryResponse<serde_json::Value> = self.client.query(query).await?;
+        let value = json!({
+            "totalSize": res.total_size,
+            "done": res.done,
+            "nextRecordsUrl": res.next_records_url,
+            "records": res.records,
+        });
+        if res.done {
+            self.insert(key, value.clone(), None);
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Describes all available objects
+    pub async fn describe_global(&self) -> Result<DescribeGlobalResponse, Error> {
+        let value = self
+            .describe_path(self.key("describe_global", ""), "sobjects/")
+            .await?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Describes specific object
+    pub async fn describe(&self, sobject_name: &str) -> Result<serde_json::Value, Error> {
+        self.describe_path(
+            self.key("describe", sobject_name),
+            &format!("sobjects/{}/describe", sobject_name),
+        )
+        .await
+    }
+
+    pub fn invalidate_query(&self, query: &str) {
+        self.remove(&self.key("query", query));
+    }
+
+    pub fn invalidate_describe(&self, sobject_name: &str) {
+        self.remove(&self.key("describe", sobject_name));
+    }
+
+    pub fn invalidate_describe_global(&self) {
+        self.remove(&self.key("describe_global", ""));
+    }
+
+    /// Drops all cached results
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Returns the number of cached results, including expired ones
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    async fn describe_path(&self, key: String, path: &str) -> Result<serde_json::Value, Error> {
+        let cached = self.lookup(&key);
+        let last_modified = match &cached {
+            Some((value, true, _)) => return Ok(value.clone()),
+            Some((_, false, last_modified)) => last_modified.clone(),
+            None => None,
+        };
+
+        match self
+            .client
+            .describe_if_modified_since(path, last_modified.as_deref())
+            .await?
+        {
+            Some((value, date)) => {
+                self.insert(key, value.clone(), Some(date));
+                Ok(value)
+            }
+            // Not modified, so the cached value is fresh again
+            None => match cached {
+                Some((value, _, _)) => {
+                    self.insert(key, value.clone(), last_modified);
+                    Ok(value)
+                }
+                // The request was not conditional, e.g. a proxy answered it
+                None => Err(Error::HTTPError(format!(
+                    "unexpected 304 Not Modified for {}",
+                    path
+                ))),
+            },
+        }
+    }
+
+    fn key(&self, kind: &str, text: &str) -> String {
+        format!("{}\n{}\n{}", self.client.version(), kind, text)
+    }
+
+    /// Returns the cached value, whether it is within its time to live and
+    /// its last modified date
+    fn lookup(&self, key: &str) -> Option<(serde_json::Value, bool, Option<String>)> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let entry = entries.get_mut(key)?;
+        entry.last_used = Instant::now();
+        Some((
+            entry.value.clone(),
+            entry.fetched_at.elapsed() < self.ttl,
+            entry.last_modified.clone(),
+        ))
+    }
+
+    fn remove(&self, key: &str) {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(key);
+    }
+
+    fn insert(&self, key: String, value: serde_json::Value, last_modified: Option<String>) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if !entries.contains_key(&key) && entries.len() >= self.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        let now = Instant::now();
+        entries.insert(
+            key,
+            Entry {
+                value,
+                fetched_at: now,
+                last_used: now,
+                last_modified,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachedClient;
+    use crate::errors::Error;
+    use crate::response::QueryResponse;
+    use mockito::{mock, Matcher};
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Account {
+        name: String,
+    }
+
+    fn create_test_client() -> Arc<crate::Client> {
+        let mut client = crate::Client::new(None, None);
+        client.set_instance_url(&mockito::server_url());
+        client.set_access_token("this_is_access_token").unwrap();
+        Arc::new(client)
+    }
+
+    fn mock_query(query: &str, name: &str) -> mockito::Mock {
+        mock("GET", "/services/data/v44.0/query/")
+            .match_query(Matcher::UrlEncoded("q".to_string(), query.to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "totalSize": 1,
+                    "done": true,
+                    "records": [{"Name": name}],
+                })
+                .to_string(),
+            )
+    }
+
+    #[tokio::test]
+    async fn query_cached_until_invalidated() -> Result<(), Error> {
+        let m = mock_query("SELECT Name FROM Account", "foo")
+            .expect(2)
+            .create();
+
+        let cache = CachedClient::new(create_test_client(), Duration::from_secs(60), 10);
+        let r: QueryResponse<Account> = cache.query("SELECT Name FROM Account").await?;
+        assert_eq!("foo", r.records[0].name);
+        let r: QueryResponse<Account> = cache.query("SELECT Name FROM Account").await?;
+        assert_eq!("foo", r.records[0].name);
+        assert_eq!(1, cache.len());
+
+        cache.invalidate_query("SELECT Name FROM Account");
+        assert!(cache.is_empty());
+        let _: QueryResponse<Account> = cache.query("SELECT Name FROM Account").await?;
+        m.assert();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_expired_and_evicted() -> Result<(), Error> {
+        let m1 = mock_query("SELECT Name FROM Account", "foo")
+            .expect(2)
+            .create();
+        let _m2 = mock_query("SELECT Name FROM Contact", "bar").create();
+
+        let cache = CachedClient::new(create_test_client(), Duration::from_secs(0), 1);
+        let _: QueryResponse<Account> = cache.query("SELECT Name FROM Account").await?;
+        let _: QueryResponse<Account> = cache.query("SELECT Name FROM Account").await?;
+        m1.assert();
+
+        let r: QueryResponse<Account> = cache.query("SELECT Name FROM Contact").await?;
+        assert_eq!("bar", r.records[0].name);
+        assert_eq!(1, cache.len());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_with_more_batches_not_cached() -> Result<(), Error> {
+        let m = mock("GET", "/services/data/v44.0/query/")
+            .match_query(Matcher::UrlEncoded(
+                "q".to_string(),
+                "SELECT Name FROM Lead".to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "totalSize": 2,
+                    "done": false,
+                    "nextRecordsUrl": "/services/data/v44.0/query/01gxx-2000",
+                    "records": [{"Name": "foo"}],
+                })
+                .to_string(),
+            )
+            .expect(2)
+            .create();
+
+        let cache = CachedClient::new(create_test_client(), Duration::from_secs(60), 10);
+        for _ in 0..2 {
+            let r: QueryResponse<Account> = cache.query("SELECT Name FROM Lead").await?;
+            assert_eq!("foo", r.records[0].name);
+        }
+        assert!(cache.is_empty());
+        m.assert();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn describe_revalidated() -> Result<(), Error> {
+        let m1 = mock("GET", "/services/data/v44.0/sobjects/Account/describe")
+            .match_header("if-modified-since", Matcher::Missing)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("date", "Tue, 01 Oct 2019 00:00:00 GMT")
+            .with_body(json!({"name": "Account"}).to_string())
+            .expect(1)
+            .create();
+        let m2 = mock("GET", "/services/data/v44.0/sobjects/Account/describe")
+            .match_header("if-modified-since", "Tue, 01 Oct 2019 00:00:00 GMT")
+            .with_status(304)
+            .expect(2)
+            .create();
+
+        let cache = CachedClient::new(create_test_client(), Duration::from_secs(0), 10);
+        for _ in 0..3 {
+            let r = cache.describe("Account").await?;
+            assert_eq!("Account", r["name"]);
+        }
+        m1.assert();
+        m2.assert();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn describe_not_modified_without_cache() -> Result<(), Error> {
+        let _m = mock("GET", "/services/data/v44.0/sobjects/Contact/describe")
+            .with_status(304)
+            .create();
+
+        let cache = CachedClient::new(create_test_client(), Duration::from_secs(60), 10);
+        assert!(matches!(
+            cache.describe("Contact").await,
+            Err(Error::HTTPError(_))
+        ));
+        assert!(cache.is_empty());
+
+        Ok(())
+    }
+}
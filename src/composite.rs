@@ -0,0 +1,904 @@
+//! Requests of the Composite API
+//!
+//! Subrequest URLs are relative to the REST API path of the client, such as
+//! `sobjects/Account`, and may refer to the results of earlier subrequests
+//! with `@{referenceId.field}`.
+//!
+//! ```
+//! use rustforce::composite::CompositeRequest;
+//! use serde_json::json;
+//!
+//! let request = CompositeRequest::new()
+//!     .all_or_none(true)
+//!     .create("newAccount", "Account", &json!({"Name": "Acme"}))
+//!     .create(
+//!         "newContact",
+//!         "Contact",
+//!         &json!({"LastName": "Smith", "AccountId": "@{newAccount.id}"}),
+//!     );
+//! ```
+use crate::errors::Error;
+use reqwest::{Method, Url};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
+/// Maximum number of subrequests in a composite request
+pub const COMPOSITE_LIMIT: usize = 25;
+
+/// Maximum number of nodes in a graph of a composite graph request
+pub const GRAPH_NODE_LIMIT: usize = 500;
+
+/// Maximum number of subrequests in a composite batch request
+pub const BATCH_LIMIT: usize = 25;
+
+/// Maximum number of records in an SObject Tree request, counting children
+pub const TREE_RECORD_LIMIT: usize = 200;
+
+/// Maximum nesting of records in an SObject Tree request
+pub const TREE_DEPTH_LIMIT: usize = 5;
+
+/// A subrequest of a composite request
+#[derive(Debug, Clone)]
+pub struct Subrequest {
+    method: Method,
+    path: String,
+    reference_id: String,
+    body: Option<serde_json::Value>,
+    http_headers: HashMap<String, String>,
+}
+
+impl Subrequest {
+    /// `path` is relative to the REST API path, such as `sobjects/Account`
+    pub fn new(method: Method, path: &str, reference_id: &str) -> Self {
+        Subrequest {
+            method,
+            path: path.to_string(),
+            reference_id: reference_id.to_string(),
+            body: None,
+            http_headers: HashMap::new(),
+        }
+    }
+
+    pub fn body(mut self, body: serde_json::Value) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.http_headers
+            .insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub(crate) fn reference_id(&self) -> &str {
+        &self.reference_id
+    }
+
+    /// Renders the subrequest with its URL under `service_path`
+    pub(crate) fn to_json(&self, service_path: &str) -> serde_json::Value {
+        let mut subrequest = json!({
+            "method": self.method.as_str(),
+            "url": format!("{}/{}", service_path, self.path.trim_start_matches('/')),
+            "referenceId": self.reference_id,
+        });
+        if let Some(body) = self.body.as_ref() {
+            subrequest["body"] = body.clone();
+        }
+        if !self.http_headers.is_empty() {
+            subrequest["httpHeaders"] = json!(self.http_headers);
+        }
+        subrequest
+    }
+}
+
+/// Body of `/composite`
+#[derive(Debug, Clone, Default)]
+pub struct CompositeRequest {
+    all_or_none: bool,
+    collate_subrequests: bool,
+    subrequests: Vec<Subrequest>,
+    error: Option<String>,
+}
+
+impl CompositeRequest {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Rolls back every subrequest when one fails
+    pub fn all_or_none(mut self, all_or_none: bool) -> Self {
+        self.all_or_none = all_or_none;
+        self
+    }
+
+    /// Lets Salesforce run independent subrequests together
+    pub fn collate_subrequests(mut self, collate_subrequests: bool) -> Self {
+        self.collate_subrequests = collate_subrequests;
+        self
+    }
+
+    pub fn subrequest(mut self, subrequest: Subrequest) -> Self {
+        self.subrequests.push(subrequest);
+        self
+    }
+
+    /// Creates an SObject
+    pub fn create<T: Serialize>(self, reference_id: &str, sobject_name: &str, params: &T) -> Self {
+        let path = format!("sobjects/{}", sobject_name);
+        self.with_body(Method::POST, &path, reference_id, params)
+    }
+
+    /// Updates an SObject
+    pub fn update<T: Serialize>(
+        self,
+        reference_id: &str,
+        sobject_name: &str,
+        id: &str,
+        params: &T,
+    ) -> Self {
+        let path = format!("sobjects/{}/{}", sobject_name, id);
+        self.with_body(Method::PATCH, &path, reference_id, params)
+    }
+
+    /// Upserts an SObject with key
+    pub fn upsert<T: Serialize>(
+        self,
+        reference_id: &str,
+        sobject_name: &str,
+        key_name: &str,
+        key: &str,
+        params: &T,
+    ) -> Self {
+        let path = format!("sobjects/{}/{}/{}", sobject_name, key_name, key);
+        self.with_body(Method::PATCH, &path, reference_id, params)
+    }
+
+    /// Deletes an SObject
+    pub fn destroy(self, reference_id: &str, sobject_name: &str, id: &str) -> Self {
+        let path = format!("sobjects/{}/{}", sobject_name, id);
+        self.subrequest(Subrequest::new(Method::DELETE, &path, reference_id))
+    }
+
+    /// Finds a record by ID
+    pub fn find_by_id(self, reference_id: &str, sobject_name: &str, id: &str) -> Self {
+        let path = format!("sobjects/{}/{}", sobject_name, id);
+        self.subrequest(Subrequest::new(Method::GET, &path, reference_id))
+    }
+
+    /// Query record using SOQL
+    pub fn query(self, reference_id: &str, query: &str) -> Self {
+        let mut url = Url::parse("http://localhost/").unwrap();
+        url.query_pairs_mut().append_pair("q", query);
+        let path = format!("query/?{}", url.query().unwrap_or_default());
+        self.subrequest(Subrequest::new(Method::GET, &path, reference_id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.subrequests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subrequests.is_empty()
+    }
+
+    fn with_body<T: Serialize>(
+        mut self,
+        method: Method,
+        path: &str,
+        reference_id: &str,
+        params: &T,
+    ) -> Self {
+        match serde_json::to_value(params) {
+            Ok(body) => self.subrequest(Subrequest::new(method, path, reference_id).body(body)),
+            Err(e) => {
+                self.error.get_or_insert_with(|| e.to_string());
+                self
+            }
+        }
+    }
+
+    /// Checks the limits of the Composite API and renders the request body
+    pub(crate) fn to_json(&self, service_path: &str) -> Result<serde_json::Value, Error> {
+        if let Some(error) = self.error.as_ref() {
+            return Err(Error::InvalidRequest(error.clone()));
+        }
+        validate_subrequests(&self.subrequests, COMPOSITE_LIMIT)?;
+        Ok(json!({
+            "allOrNone": self.all_or_none,
+            "collateSubrequests": self.collate_subrequests,
+            "compositeRequest": self
+                .subrequests
+                .iter()
+                .map(|s| s.to_json(service_path))
+                .collect::<Vec<_>>(),
+        }))
+    }
+}
+
+/// Checks the number of subrequests and that their reference IDs are unique
+fn validate_subrequests(subrequests: &[Subrequest], limit: usize) -> Result<(), Error> {
+    if subrequests.is_empty() || subrequests.len() > limit {
+        return Err(Error::InvalidRequest(format!(
+            "{} subrequests given, 1 to {} are allowed",
+            subrequests.len(),
+            limit
+        )));
+    }
+    let mut reference_ids = HashSet::new();
+    for subrequest in subrequests {
+        if !reference_ids.insert(subrequest.reference_id()) {
+            return Err(Error::InvalidRequest(format!(
+                "duplicate reference ID {}",
+                subrequest.reference_id()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A graph of a composite graph request. Nodes may refer to earlier nodes of
+/// the same graph, and fail or succeed together
+#[derive(Debug, Clone)]
+pub struct Graph {
+    graph_id: String,
+    nodes: CompositeRequest,
+}
+
+impl Graph {
+    pub fn new(graph_id: &str) -> Self {
+        Graph {
+            graph_id: graph_id.to_string(),
+            nodes: CompositeRequest::new(),
+        }
+    }
+
+    pub fn node(mut self, subrequest: Subrequest) -> Self {
+        self.nodes = self.nodes.subrequest(subrequest);
+        self
+    }
+
+    /// Creates an SObject
+    pub fn create<T: Serialize>(
+        mut self,
+        reference_id: &str,
+        sobject_name: &str,
+        params: &T,
+    ) -> Self {
+        self.nodes = self.nodes.create(reference_id, sobject_name, params);
+        self
+    }
+
+    /// Updates an SObject
+    pub fn update<T: Serialize>(
+        mut self,
+        reference_id: &str,
+        sobject_name: &str,
+        id: &str,
+        params: &T,
+    ) -> Self {
+        self.nodes = self.nodes.update(reference_id, sobject_name, id, params);
+        self
+    }
+
+    /// Upserts an SObject with key
+    pub fn upsert<T: Serialize>(
+        mut self,
+        reference_id: &str,
+        sobject_name: &str,
+        key_name: &str,
+        key: &str,
+        params: &T,
+    ) -> Self {
+        self.nodes = self
+            .nodes
+            .upsert(reference_id, sobject_name, key_name, key, params);
+        self
+    }
+
+    /// Deletes an SObject
+    pub fn destroy(mut self, reference_id: &str, sobject_name: &str, id: &str) -> Self {
+        self.nodes = self.nodes.destroy(reference_id, sobject_name, id);
+        self
+    }
+
+    /// Finds a record by ID
+    pub fn find_by_id(mut self, reference_id: &str, sobject_name: &str, id: &str) -> Self {
+        self.nodes = self.nodes.find_by_id(reference_id, sobject_name, id);
+        self
+    }
+
+    pub fn graph_id(&self) -> &str {
+        &self.graph_id
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Checks the node limit and that references point to earlier nodes,
+    /// then renders the graph
+    fn to_json(&self, service_path: &str) -> Result<serde_json::Value, Error> {
+        if let Some(error) = self.nodes.error.as_ref() {
+            return Err(Error::InvalidRequest(error.clone()));
+        }
+        let nodes = &self.nodes.subrequests;
+        validate_subrequests(nodes, GRAPH_NODE_LIMIT).map_err(|e| match e {
+            Error::InvalidRequest(e) => {
+                Error::InvalidRequest(format!("graph {}: {}", self.graph_id, e))
+            }
+            e => e,
+        })?;
+
+        let mut defined = HashSet::new();
+        for node in nodes {
+            let mut texts = vec![node.path.as_str()];
+            if let Some(body) = node.body.as_ref() {
+                collect_strings(body, &mut texts);
+            }
+            for reference in texts.into_iter().flat_map(references) {
+                if !defined.contains(reference) {
+                    return Err(Error::InvalidRequest(format!(
+                        "graph {}: node {} refers to {}, which is not an earlier node",
+                        self.graph_id, node.reference_id, reference
+                    )));
+                }
+            }
+            defined.insert(node.reference_id());
+        }
+
+        Ok(json!({
+            "graphId": self.graph_id,
+            "compositeRequest": nodes
+                .iter()
+                .map(|s| s.to_json(service_path))
+                .collect::<Vec<_>>(),
+        }))
+    }
+}
+
+/// Checks that graph IDs are unique and renders the body of `/composite/graph`
+pub(crate) fn graphs_to_json(
+    graphs: &[Graph],
+    service_path: &str,
+) -> Result<serde_json::Value, Error> {
+    if graphs.is_empty() {
+        return Err(Error::InvalidRequest("no graphs given".to_string()));
+    }
+    let mut graph_ids = HashSet::new();
+    let mut rendered = Vec::with_capacity(graphs.len());
+    for graph in graphs {
+        if !graph_ids.insert(graph.graph_id()) {
+            return Err(Error::InvalidRequest(format!(
+                "duplicate graph ID {}",
+                graph.graph_id()
+            )));
+        }
+        rendered.push(graph.to_json(service_path)?);
+    }
+    Ok(json!({ "graphs": rendered }))
+}
+
+fn collect_strings<'a>(value: &'a serde_json::Value, strings: &mut Vec<&'a str>) {
+    match value {
+        serde_json::Value::String(s) => strings.push(s),
+        serde_json::Value::Array(values) => {
+            values.iter().for_each(|v| collect_strings(v, strings));
+        }
+        serde_json::Value::Object(map) => map.values().for_each(|v| collect_strings(v, strings)),
+        _ => {}
+    }
+}
+
+/// Returns the reference IDs of the `@{referenceId.field}` references in the
+/// text
+fn references(text: &str) -> Vec<&str> {
+    text.split("@{")
+        .skip(1)
+        .filter_map(|s| s.split(['.', '}', '[']).next())
+        .collect()
+}
+
+/// Body of `/composite/batch`. Subrequests are independent of each other and
+/// cannot refer to earlier results
+#[derive(Debug, Clone, Default)]
+pub struct BatchRequest {
+    halt_on_error: bool,
+    subrequests: CompositeRequest,
+}
+
+impl BatchRequest {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Skips the remaining subrequests after one fails
+    pub fn halt_on_error(mut self, halt_on_error: bool) -> Self {
+        self.halt_on_error = halt_on_error;
+        self
+    }
+
+    /// `path` is relative to the REST API path, such as `sobjects/Account`
+    pub fn subrequest(
+        mut self,
+        method: Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Self {
+        let mut subrequest = Subrequest::new(method, path, "");
+        subrequest.body = body;
+        self.subrequests = self.subrequests.subrequest(subrequest);
+        self
+    }
+
+    /// Creates an SObject
+    pub fn create<T: Serialize>(mut self, sobject_name: &str, params: &T) -> Self {
+        self.subrequests = self.subrequests.create("", sobject_name, params);
+        self
+    }
+
+    /// Updates an SObject
+    pub fn update<T: Serialize>(mut self, sobject_name: &str, id: &str, params: &T) -> Self {
+        self.subrequests = self.subrequests.update("", sobject_name, id, params);
+        self
+    }
+
+    /// Upserts an SObject with key
+    pub fn upsert<T: Serialize>(
+        mut self,
+        sobject_name: &str,
+        key_name: &str,
+        key: &str,
+        params: &T,
+    ) -> Self {
+        self.subrequests = self
+            .subrequests
+            .upsert("", sobject_name, key_name, key, params);
+        self
+    }
+
+    /// Deletes an SObject
+    pub fn destroy(mut self, sobject_name: &str, id: &str) -> Self {
+        self.subrequests = self.subrequests.destroy("", sobject_name, id);
+        self
+    }
+
+    /// Finds a record by ID
+    pub fn find_by_id(mut self, sobject_name: &str, id: &str) -> Self {
+        self.subrequests = self.subrequests.find_by_id("", sobject_name, id);
+        self
+    }
+
+    /// Query record using SOQL
+    pub fn query(mut self, query: &str) -> Self {
+        self.subrequests = self.subrequests.query("", query);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.subrequests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subrequests.is_empty()
+    }
+
+    /// Checks the limits of the Composite Batch API and renders the request
+    /// body. Batch URLs start with the API version such as `v44.0`
+    pub(crate) fn to_json(&self, version: &str) -> Result<serde_json::Value, Error> {
+        if let Some(error) = self.subrequests.error.as_ref() {
+            return Err(Error::InvalidRequest(error.clone()));
+        }
+        let subrequests = &self.subrequests.subrequests;
+        if subrequests.is_empty() || subrequests.len() > BATCH_LIMIT {
+            return Err(Error::InvalidRequest(format!(
+                "{} subrequests given, 1 to {} are allowed",
+                subrequests.len(),
+                BATCH_LIMIT
+            )));
+        }
+        Ok(json!({
+            "haltOnError": self.halt_on_error,
+            "batchRequests": subrequests
+                .iter()
+                .map(|s| {
+                    let mut subrequest = json!({
+                        "method": s.method.as_str(),
+                        "url": format!("{}/{}", version, s.path.trim_start_matches('/')),
+                    });
+                    if let Some(body) = s.body.as_ref() {
+                        subrequest["richInput"] = body.clone();
+                    }
+                    subrequest
+                })
+                .collect::<Vec<_>>(),
+        }))
+    }
+}
+
+/// A record of an SObject Tree request with its child records
+#[derive(Debug, Clone)]
+pub struct TreeRecord {
+    sobject_type: String,
+    reference_id: String,
+    fields: serde_json::Map<String, serde_json::Value>,
+    children: Vec<(String, Vec<TreeRecord>)>,
+    error: Option<String>,
+}
+
+impl TreeRecord {
+    pub fn new(sobject_type: &str, reference_id: &str) -> Self {
+        TreeRecord {
+            sobject_type: sobject_type.to_string(),
+            reference_id: reference_id.to_string(),
+            fields: serde_json::Map::new(),
+            children: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn field<V: Into<serde_json::Value>>(mut self, name: &str, value: V) -> Self {
+        self.fields.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Sets the fields of a value that serializes to a JSON object
+    pub fn fields<T: Serialize>(mut self, fields: &T) -> Self {
+        match serde_json::to_value(fields) {
+            Ok(serde_json::Value::Object(fields)) => self.fields.extend(fields),
+            Ok(_) => {
+                self.error
+                    .get_or_insert_with(|| "fields must serialize to a JSON object".to_string());
+            }
+            Err(e) => {
+                self.error.get_or_insert_with(|| e.to_string());
+            }
+        }
+        self
+    }
+
+    /// Adds a child record under the child relationship such as `Contacts`
+    pub fn child(mut self, relationship_name: &str, record: TreeRecord) -> Self {
+        match self
+            .children
+            .iter_mut()
+            .find(|(name, _)| name == relationship_name)
+        {
+            Some((_, records)) => records.push(record),
+            None => self
+                .children
+                .push((relationship_name.to_string(), vec![record])),
+        }
+        self
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut record = self.fields.clone();
+        record.insert(
+            "attributes".to_string(),
+            json!({ "type": self.sobject_type, "referenceId": self.reference_id }),
+        );
+        for (relationship_name, records) in self.children.iter() {
+            record.insert(
+                relationship_name.clone(),
+                json!({ "records": records.iter().map(TreeRecord::to_json).collect::<Vec<_>>() }),
+            );
+        }
+        serde_json::Value::Object(record)
+    }
+
+    /// Checks the record and its children, counting records and reference IDs
+    fn validate<'a>(
+        &'a self,
+        depth: usize,
+        reference_ids: &mut HashSet<&'a str>,
+    ) -> Result<(), Error> {
+        if let Some(error) = self.error.as_ref() {
+            return Err(Error::InvalidRequest(format!(
+                "record {}: {}",
+                self.reference_id, error
+            )));
+        }
+        if depth > TREE_DEPTH_LIMIT {
+            return Err(Error::InvalidRequest(format!(
+                "record {} is nested deeper than {} levels",
+                self.reference_id, TREE_DEPTH_LIMIT
+            )));
+        }
+        if !reference_ids.insert(&self.reference_id) {
+            return Err(Error::InvalidRequest(format!(
+                "duplicate reference ID {}",
+                self.reference_id
+            )));
+        }
+        for (_, records) in self.children.iter() {
+            for record in records {
+                record.validate(depth + 1, reference_ids)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the limits of the SObject Tree API and renders the request body
+pub(crate) fn tree_to_json(records: &[TreeRecord]) -> Result<serde_json::Value, Error> {
+    let mut reference_ids = HashSet::new();
+    for record in records {
+        record.validate(1, &mut reference_ids)?;
+    }
+    if reference_ids.is_empty() || reference_ids.len() > TREE_RECORD_LIMIT {
+        return Err(Error::InvalidRequest(format!(
+            "{} records given, 1 to {} are allowed",
+            reference_ids.len(),
+            TREE_RECORD_LIMIT
+        )));
+    }
+    Ok(json!({ "records": records.iter().map(TreeRecord::to_json).collect::<Vec<_>>() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        graphs_to_json, tree_to_json, BatchRequest, CompositeRequest, Graph, Subrequest, TreeRecord,
+    };
+    use crate::errors::Error;
+    use reqwest::Method;
+    use serde_json::json;
+
+    #[test]
+    fn to_json() -> Result<(), Error> {
+        let request = CompositeRequest::new()
+            .all_or_none(true)
+            .create("newAccount", "Account", &json!({"Name": "Acme"}))
+            .update(
+                "updateContact",
+                "Contact",
+                "003",
+                &json!({"AccountId": "@{newAccount.id}"}),
+            )
+            .query("accounts", "SELECT Id FROM Account WHERE Name = 'Acme'")
+            .subrequest(
+                Subrequest::new(Method::GET, "/sobjects/Account/describe", "describe")
+                    .header("If-Modified-Since", "Tue, 01 Oct 2019 00:00:00 GMT"),
+            );
+        assert_eq!(
+            json!({
+                "allOrNone": true,
+                "collateSubrequests": false,
+                "compositeRequest": [{
+                    "method": "POST",
+                    "url": "/services/data/v44.0/sobjects/Account",
+                    "referenceId": "newAccount",
+                    "body": {"Name": "Acme"},
+                }, {
+                    "method": "PATCH",
+                    "url": "/services/data/v44.0/sobjects/Contact/003",
+                    "referenceId": "updateContact",
+                    "body": {"AccountId": "@{newAccount.id}"},
+                }, {
+                    "method": "GET",
+                    "url": "/services/data/v44.0/query/?q=SELECT+Id+FROM+Account+WHERE+Name+%3D+%27Acme%27",
+                    "referenceId": "accounts",
+                }, {
+                    "method": "GET",
+                    "url": "/services/data/v44.0/sobjects/Account/describe",
+                    "referenceId": "describe",
+                    "httpHeaders": {"If-Modified-Since": "Tue, 01 Oct 2019 00:00:00 GMT"},
+                }],
+            }),
+            request.to_json("/services/data/v44.0")?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate() {
+        let request = CompositeRequest::new();
+        assert!(matches!(
+            request.to_json("/services/data/v44.0"),
+            Err(Error::InvalidRequest(_))
+        ));
+
+        let request = (0..26).fold(CompositeRequest::new(), |request, i| {
+            request.destroy(&format!("ref{}", i), "Account", "001")
+        });
+        assert!(matches!(
+            request.to_json("/services/data/v44.0"),
+            Err(Error::InvalidRequest(_))
+        ));
+
+        let request = CompositeRequest::new()
+            .destroy("ref", "Account", "001")
+            .destroy("ref", "Account", "002");
+        assert!(matches!(
+            request.to_json("/services/data/v44.0"),
+            Err(Error::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn graphs() -> Result<(), Error> {
+        let graphs = vec![
+            Graph::new("g1")
+                .create("newAccount", "Account", &json!({"Name": "Acme"}))
+                .create(
+                    "newContact",
+                    "Contact",
+                    &json!({"LastName": "Smith", "AccountId": "@{newAccount.id}"}),
+                ),
+            Graph::new("g2").destroy("deleteAccount", "Account", "001"),
+        ];
+        assert_eq!(
+            json!({
+                "graphs": [{
+                    "graphId": "g1",
+                    "compositeRequest": [{
+                        "method": "POST",
+                        "url": "/services/data/v44.0/sobjects/Account",
+                        "referenceId": "newAccount",
+                        "body": {"Name": "Acme"},
+                    }, {
+                        "method": "POST",
+                        "url": "/services/data/v44.0/sobjects/Contact",
+                        "referenceId": "newContact",
+                        "body": {"LastName": "Smith", "AccountId": "@{newAccount.id}"},
+                    }],
+                }, {
+                    "graphId": "g2",
+                    "compositeRequest": [{
+                        "method": "DELETE",
+                        "url": "/services/data/v44.0/sobjects/Account/001",
+                        "referenceId": "deleteAccount",
+                    }],
+                }],
+            }),
+            graphs_to_json(&graphs, "/services/data/v44.0")?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_graphs() {
+        let invalid = |graphs: Vec<Graph>| {
+            matches!(
+                graphs_to_json(&graphs, "/services/data/v44.0"),
+                Err(Error::InvalidRequest(_))
+            )
+        };
+
+        assert!(invalid(vec![]));
+        assert!(invalid(vec![Graph::new("g1")]));
+        assert!(invalid(vec![
+            Graph::new("g1").destroy("a", "Account", "001"),
+            Graph::new("g1").destroy("b", "Account", "002"),
+        ]));
+        assert!(invalid(vec![(0..501)
+            .fold(Graph::new("g1"), |graph, i| {
+                graph.destroy(&format!("ref{}", i), "Account", "001")
+            })]));
+        assert!(invalid(vec![Graph::new("g1")
+            .update(
+                "a",
+                "Contact",
+                "003",
+                &json!({"AccountId": "@{newAccount.id}"})
+            )
+            .create("newAccount", "Account", &json!({"Name": "Acme"}))]));
+        assert!(invalid(vec![Graph::new("g1").node(Subrequest::new(
+            Method::GET,
+            "sobjects/Account/@{missing.id}",
+            "a"
+        ))]));
+
+        let graph = (0..500).fold(Graph::new("g1"), |graph, i| {
+            graph.destroy(&format!("ref{}", i), "Account", "001")
+        });
+        assert!(!invalid(vec![graph]));
+    }
+
+    #[test]
+    fn tree() -> Result<(), Error> {
+        let records = vec![TreeRecord::new("Account", "ref1")
+            .field("Name", "Acme")
+            .fields(&json!({"NumberOfEmployees": 100}))
+            .child(
+                "Contacts",
+                TreeRecord::new("Contact", "ref2").field("LastName", "Smith"),
+            )
+            .child(
+                "Contacts",
+                TreeRecord::new("Contact", "ref3").field("LastName", "Evans"),
+            )];
+        assert_eq!(
+            json!({
+                "records": [{
+                    "attributes": {"type": "Account", "referenceId": "ref1"},
+                    "Name": "Acme",
+                    "NumberOfEmployees": 100,
+                    "Contacts": {
+                        "records": [{
+                            "attributes": {"type": "Contact", "referenceId": "ref2"},
+                            "LastName": "Smith",
+                        }, {
+                            "attributes": {"type": "Contact", "referenceId": "ref3"},
+                            "LastName": "Evans",
+                        }],
+                    },
+                }],
+            }),
+            tree_to_json(&records)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_tree() {
+        let invalid = |records: Vec<TreeRecord>| {
+            matches!(tree_to_json(&records), Err(Error::InvalidRequest(_)))
+        };
+
+        assert!(invalid(vec![]));
+        assert!(invalid(vec![
+            TreeRecord::new("Account", "ref1"),
+            TreeRecord::new("Account", "ref1"),
+        ]));
+        assert!(invalid(vec![TreeRecord::new("Account", "ref1").fields(&1)]));
+        assert!(invalid(
+            (0..201)
+                .map(|i| TreeRecord::new("Account", &format!("ref{}", i)))
+                .collect()
+        ));
+        let nested = |depth: usize| {
+            (1..depth).fold(TreeRecord::new("Account", "ref0"), |record, i| {
+                TreeRecord::new("Account", &format!("ref{}", i)).child("ChildAccounts", record)
+            })
+        };
+        assert!(!invalid(vec![nested(5)]));
+        assert!(invalid(vec![nested(6)]));
+    }
+
+    #[test]
+    fn batch() -> Result<(), Error> {
+        let request = BatchRequest::new()
+            .halt_on_error(true)
+            .find_by_id("Account", "001")
+            .update("Account", "001", &json!({"Name": "Acme"}))
+            .subrequest(Method::GET, "limits", None);
+        assert_eq!(
+            json!({
+                "haltOnError": true,
+                "batchRequests": [
+                    {"method": "GET", "url": "v44.0/sobjects/Account/001"},
+                    {
+                        "method": "PATCH",
+                        "url": "v44.0/sobjects/Account/001",
+                        "richInput": {"Name": "Acme"},
+                    },
+                    {"method": "GET", "url": "v44.0/limits"},
+                ],
+            }),
+            request.to_json("v44.0")?
+        );
+
+        assert!(matches!(
+            BatchRequest::new().to_json("v44.0"),
+            Err(Error::InvalidRequest(_))
+        ));
+        let request = (0..26).fold(BatchRequest::new(), |request, _| {
+            request.find_by_id("Account", "001")
+        });
+        assert!(matches!(
+            request.to_json("v44.0"),
+            Err(Error::InvalidRequest(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -10,6 +10,13 @@ pub enum Error {
     ErrorResponses(Vec<ErrorResponse>),
     DescribeError(ErrorResponse),
     LoginError(ErrorResponse),
+    JwtError(String),
+    AuthorizationError(String),
+    DeviceFlowError(TokenErrorResponse),
+    TokenStoreError(String),
+    MissingCredential(&'static str),
+    ConfigError(String),
+    InvalidRequest(String),
 }
 
 impl std::error::Error for Error {}
@@ -24,6 +31,13 @@ impl fmt::Display for Error {
             Error::ErrorResponses(resp) => write!(f, "Error response from Salesforce {:?}", resp),
             Error::DescribeError(resp) => write!(f, "Error completing describe {:?}", resp),
             Error::LoginError(resp) => write!(f, "Error logging in {:?}", resp),
+            Error::JwtError(resp) => write!(f, "Could not create JWT assertion {}", resp),
+            Error::AuthorizationError(resp) => write!(f, "Authorization failed {}", resp),
+            Error::DeviceFlowError(resp) => write!(f, "Device flow failed {:?}", resp),
+            Error::TokenStoreError(resp) => write!(f, "Token store failed {}", resp),
+            Error::MissingCredential(name) => write!(f, "{} is not set on the client", name),
+            Error::ConfigError(resp) => write!(f, "Invalid configuration {}", resp),
+            Error::InvalidRequest(resp) => write!(f, "Invalid request {}", resp),
         }
     }
 }
@@ -45,3 +59,9 @@ impl From<serde_json::Error> for Error {
         Error::DeserializeError(e.to_string())
     }
 }
+
+impl From<jsonwebtoken::errors::Error> for Error {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        Error::JwtError(e.to_string())
+    }
+}
@@ -38,7 +38,7 @@
 //!     let username = env::var("SFDC_USERNAME").unwrap();
 //!     let password = env::var("SFDC_PASSWORD").unwrap();
 //!
-//!     let mut client = Client::new(client_id, client_secret);
+//!     let mut client = Client::new(Some(client_id), Some(client_secret));
 //!     client.login_with_credential(username, password).await?;
 
 //!     let mut params = HashMap::new();
@@ -50,9 +50,18 @@
 //!     Ok(())
 //! }
 //! ```
+pub mod cache;
 pub mod client;
+pub mod composite;
 pub mod errors;
+pub mod oauth;
+pub mod registry;
 pub mod response;
+pub mod search;
+mod soap;
+pub mod soql;
+pub mod stream;
+pub mod token_store;
 pub mod utils;
 
 pub type Client = client::Client;
@@ -0,0 +1,228 @@
+//! Helpers for the OAuth 2.0 Web Server (authorization code) flow
+//!
+//! Use [`Pkce::new`] and [`Client::authorize_url`](crate::Client::authorize_url)
+//! to send the user to Salesforce, then [`RedirectListener`] to receive the
+//! code on a loopback address and
+//! [`Client::login_with_authorization_code`](crate::Client::login_with_authorization_code)
+//! to exchange it for a token.
+use crate::errors::Error;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng, RngCore};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const REDIRECT_RESPONSE_BODY: &str =
+    "<html><body>Authorization finished. You can close this window.</body></html>";
+
+/// PKCE code verifier and its S256 code challenge
+#[derive(Debug, Clone)]
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    /// Generates a random code verifier
+    pub fn new() -> Self {
+        let mut bytes = [0u8; 32];
+        thread_rng().fill_bytes(&mut bytes);
+        Self::from_verifier(&URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    /// Builds the code challenge for an existing code verifier
+    pub fn from_verifier(verifier: &str) -> Self {
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Pkce {
+            verifier: verifier.to_string(),
+            challenge,
+        }
+    }
+}
+
+impl Default for Pkce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generates a random value for the `state` parameter
+pub fn generate_state() -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Listens on a loopback address for the redirect that ends the Web Server
+/// flow
+pub struct RedirectListener {
+    listener: TcpListener,
+    path: String,
+}
+
+impl RedirectListener {
+    /// Binds the listener. Use port 0 to let the OS pick a free port, and
+    /// register the resulting `redirect_uri` as callback URL on the connected
+    /// app
+    pub async fn bind(addr: SocketAddr, path: &str) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| Error::AuthorizationError(e.to_string()))?;
+        Ok(RedirectListener {
+            listener,
+            path: path.to_string(),
+        })
+    }
+
+    /// Redirect URI to pass to the authorize URL and the code exchange
+    pub fn redirect_uri(&self) -> Result<String, Error> {
+        let addr = self
+            .listener
+            .local_addr()
+            .map_err(|e| Error::AuthorizationError(e.to_string()))?;
+        Ok(format!("http://{}{}", addr, self.path))
+    }
+
+    /// Waits for the browser to be redirected back and returns the
+    /// authorization code after checking the state
+    pub async fn wait_for_code(&self, state: &str) -> Result<String, Error> {
+        loop {
+            let (mut stream, _) = self
+                .listener
+                .accept()
+                .await
+                .map_err(|e| Error::AuthorizationError(e.to_string()))?;
+            let mut buf = vec![0u8; 8192];
+            let n = stream
+                .read(&mut buf)
+                .await
+                .map_err(|e| Error::AuthorizationError(e.to_string()))?;
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let target = match request
+                .lines()
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+            {
+                Some(target) => target.to_string(),
+                None => continue,
+            };
+            let url = Url::parse(&format!("http://localhost{}", target))
+                .map_err(|e| Error::AuthorizationError(e.to_string()))?;
+            if url.path() != self.path {
+                let _ = stream
+                    .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                    .await;
+                continue;
+            }
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                REDIRECT_RESPONSE_BODY.len(),
+                REDIRECT_RESPONSE_BODY
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            return parse_redirect(&url, state);
+        }
+    }
+}
+
+fn parse_redirect(url: &Url, state: &str) -> Result<String, Error> {
+    let mut code = None;
+    let mut returned_state = None;
+    let mut error = None;
+    let mut error_description = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "code" => code = Some(value.into_owned()),
+            "state" => returned_state = Some(value.into_owned()),
+            "error" => error = Some(value.into_owned()),
+            "error_description" => error_description = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+    if let Some(error) = error {
+        return Err(Error::AuthorizationError(format!(
+            "{}: {}",
+            error,
+            error_description.unwrap_or_default()
+        )));
+    }
+    if returned_state.as_deref() != Some(state) {
+        return Err(Error::AuthorizationError("state mismatch".to_string()));
+    }
+    code.ok_or_else(|| Error::AuthorizationError("code is missing".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pkce, RedirectListener};
+    use crate::errors::Error;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    #[test]
+    fn pkce_challenge() {
+        let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW-gFWw-dXk");
+        assert_eq!(
+            "QS_gtOR219mbV-jVSgUVLJe5s0Fa99ItKYwST78qk2g",
+            pkce.challenge
+        );
+        assert_eq!(43, Pkce::new().verifier.len());
+    }
+
+    #[tokio::test]
+    async fn redirect_listener() -> Result<(), Error> {
+        let listener = RedirectListener::bind("127.0.0.1:0".parse().unwrap(), "/callback").await?;
+        let redirect_uri = listener.redirect_uri()?;
+        let addr = redirect_uri
+            .trim_start_matches("http://")
+            .trim_end_matches("/callback")
+            .to_string();
+
+        let browser = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(b"GET /callback?code=aPrx&state=xyz HTTP/1.1\r\nHost: localhost\r\n\r\n")
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        });
+
+        let code = listener.wait_for_code("xyz").await?;
+        assert_eq!("aPrx", code);
+        assert!(browser.await.unwrap().starts_with("HTTP/1.1 200 OK"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn redirect_listener_state_mismatch() -> Result<(), Error> {
+        let listener = RedirectListener::bind("127.0.0.1:0".parse().unwrap(), "/callback").await?;
+        let addr = listener
+            .redirect_uri()?
+            .trim_start_matches("http://")
+            .trim_end_matches("/callback")
+            .to_string();
+
+        tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(b"GET /callback?code=aPrx&state=other HTTP/1.1\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let r = listener.wait_for_code("xyz").await;
+        assert!(matches!(r, Err(Error::AuthorizationError(_))));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+//! Registry of clients for several orgs, keyed by alias
+//!
+//! The registry is loaded from a TOML or JSON file such as
+//!
+//! ```toml
+//! [orgs.prod]
+//! client_id = "xxx"
+//! client_secret = "yyy"
+//! auth = { method = "password", username = "user@example.com", password = "pass" }
+//!
+//! [orgs.dev]
+//! login_endpoint = "https://test.salesforce.com"
+//! version = "v52.0"
+//! client_id = "xxx"
+//! auth = { method = "jwt", username = "user@example.com.dev", private_key_path = "server.key" }
+//! ```
+//!
+//! and logs in to an org the first time its client is requested.
+use crate::client::Client;
+use crate::errors::Error;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::OnceCell;
+
+/// Configuration of all orgs, keyed by alias
+#[derive(Deserialize, Debug, Clone)]
+pub struct RegistryConfig {
+    pub orgs: HashMap<String, OrgConfig>,
+}
+
+/// Configuration of a single org
+#[derive(Deserialize, Debug, Clone)]
+pub struct OrgConfig {
+    pub login_endpoint: Option<String>,
+    pub version: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub auth: AuthMethod,
+}
+
+/// How the registry logs in to an org
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum AuthMethod {
+    Password {
+        username: String,
+        password: String,
+    },
+    Soap {
+        username: String,
+        password: String,
+    },
+    Jwt {
+        username: String,
+        private_key_path: PathBuf,
+    },
+    ClientCredentials,
+    RefreshToken {
+        refresh_token: String,
+    },
+    AccessToken {
+        access_token: String,
+        instance_url: String,
+    },
+}
+
+/// Hands out logged in clients by org alias
+pub struct OrgRegistry {
+    configs: HashMap<String, OrgConfig>,
+    /// One cell per alias, so logging in to one org doesn't block the others
+    clients: Mutex<HashMap<String, Arc<OnceCell<Arc<Client>>>>>,
+}
+
+impl OrgRegistry {
+    pub fn new(config: RegistryConfig) -> Self {
+        OrgRegistry {
+            configs: config.orgs,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Loads the registry from a JSON string
+    pub fn from_json(config: &str) -> Result<Self, Error> {
+        Ok(Self::new(
+            serde_json::from_str(config).map_err(|e| Error::ConfigError(e.to_string()))?,
+        ))
+    }
+
+    /// Loads the registry from a TOML string
+    pub fn from_toml(config: &str) -> Result<Self, Error> {
+        Ok(Self::new(
+            toml::from_str(config).map_err(|e| Error::ConfigError(e.to_string()))?,
+        ))
+    }
+
+    /// Loads the registry from a `.toml` or `.json` file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let config = fs::read_to_string(path).map_err(|e| Error::ConfigError(e.to_string()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&config),
+            Some("json") => Self::from_json(&config),
+            _ => Err(Error::ConfigError(format!(
+                "unsupported config file {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Returns the aliases of all configured orgs
+    pub fn aliases(&self) -> Vec<&str> {
+        self.configs.keys().map(|alias| alias.as_str()).collect()
+    }
+
+    /// Returns the client for the org, logging in on first use
+    pub async fn client(&self, alias: &str) -> Result<Arc<Client>, Error> {
+        let config = self
+            .configs
+            .get(alias)
+            .ok_or_else(|| Error::ConfigError(format!("unknown org {}", alias)))?;
+        let cell = self
+            .clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(alias.to_string())
+            .or_default()
+            .clone();
+        // Concurrent callers for the same alias wait for a single login. A
+        // failed login leaves the cell empty so the next call tries again
+        let client = cell
+            .get_or_try_init(|| async { Ok::<_, Error>(Arc::new(login(config).await?)) })
+            .await?;
+        Ok(client.clone())
+    }
+
+    /// Drops the client for the org so the next `client` call logs in again
+    pub fn invalidate(&self, alias: &str) {
+        self.clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(alias);
+    }
+}
+
+async fn login(config: &OrgConfig) -> Result<Client, Error> {
+    let mut client = Client::new(config.client_id.clone(), config.client_secret.clone());
+    if let Some(login_endpoint) = config.login_endpoint.as_ref() {
+        client.set_login_endpoint(login_endpoint);
+    }
+    if let Some(version) = config.version.as_ref() {
+        client.set_version(version);
+    }
+
+    match &config.auth {
+        AuthMethod::Password { username, password } => {
+            client
+                .login_with_credential(username.clone(), password.clone())
+                .await?;
+        }
+        AuthMethod::Soap { username, password } => {
+            client
+                .login_by_soap(username.clone(), password.clone())
+                .await?;
+        }
+        AuthMethod::Jwt {
+            username,
+            private_key_path,
+        } => {
+            let private_key =
+                fs::read(private_key_path).map_err(|e| Error::ConfigError(e.to_string()))?;
+            client
+                .login_with_jwt(username.clone(), &private_key)
+                .await?;
+        }
+        AuthMethod::ClientCredentials => {
+            client.login_with_client_credentials().await?;
+        }
+        AuthMethod::RefreshToken { refresh_token } => {
+            client.refresh(refresh_token).await?;
+        }
+        AuthMethod::AccessToken {
+            access_token,
+            instance_url,
+        } => {
+            client.set_instance_url(instance_url);
+            client.set_access_token(access_token)?;
+        }
+    }
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrgRegistry;
+    use crate::errors::Error;
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn client_from_toml() -> Result<(), Error> {
+        let _m = mock("POST", "/services/oauth2/token")
+            .match_body(Matcher::UrlEncoded(
+                "username".to_string(),
+                "user@example.com".to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "access_token": "this_is_access_token",
+                    "issued_at": "2019-10-01 00:00:00",
+                    "id": "12345",
+                    "instance_url": mockito::server_url(),
+                    "signature": "abcde",
+                    "token_type": "Bearer",
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create();
+        let _m2 = mock("GET", "/services/data/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!([]).to_string())
+            .create();
+
+        let registry = OrgRegistry::from_toml(&format!(
+            r#"
+[orgs.prod]
+login_endpoint = "{}"
+version = "v52.0"
+client_id = "aaa"
+client_secret = "bbb"
+auth = {{ method = "password", username = "user@example.com", password = "pass" }}
+"#,
+            mockito::server_url()
+        ))?;
+        assert_eq!(vec!["prod"], registry.aliases());
+
+        registry.client("prod").await?;
+        let client = registry.client("prod").await?;
+        client.versions().await?;
+        _m.assert();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn client_from_json() -> Result<(), Error> {
+        let registry = OrgRegistry::from_json(
+            &json!({
+                "orgs": {
+                    "scratch": {
+                        "auth": {
+                            "method": "access_token",
+                            "access_token": "this_is_access_token",
+                            "instance_url": "https://ap.salesforce.com",
+                        },
+                    },
+                },
+            })
+            .to_string(),
+        )?;
+        assert!(registry.client("scratch").await.is_ok());
+        registry.invalidate("scratch");
+        assert!(registry.client("scratch").await.is_ok());
+        assert!(matches!(
+            registry.client("unknown").await,
+            Err(Error::ConfigError(_))
+        ));
+        assert!(matches!(
+            OrgRegistry::from_json(r#"{"orgs": {"scratch": {}}}"#),
+            Err(Error::ConfigError(_))
+        ));
+
+        Ok(())
+    }
+}
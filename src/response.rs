@@ -1,25 +1,307 @@
 extern crate reqwest;
 
-use serde::Deserialize;
+use crate::errors::Error;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
+use std::time::SystemTime;
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryResponse<T> {
     pub total_size: i32,
     pub done: bool,
+    pub next_records_url: Option<String>,
     pub records: Vec<T>,
 }
 
+/// Records of a child relationship subquery such as
+/// `SELECT Id, (SELECT Id FROM Contacts) FROM Account`. A `null` subquery
+/// result is deserialized as an empty one
+#[derive(Debug)]
+pub struct Subquery<T> {
+    pub total_size: i32,
+    pub done: bool,
+    pub next_records_url: Option<String>,
+    pub records: Vec<T>,
+}
+
+impl<T> Default for Subquery<T> {
+    fn default() -> Self {
+        Subquery {
+            total_size: 0,
+            done: true,
+            next_records_url: None,
+            records: Vec::new(),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Subquery<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let response: Option<QueryResponse<T>> = Option::deserialize(deserializer)?;
+        Ok(response
+            .map(|r| Subquery {
+                total_size: r.total_size,
+                done: r.done,
+                next_records_url: r.next_records_url,
+                records: r.records,
+            })
+            .unwrap_or_default())
+    }
+}
+
+/// A row of an aggregate query such as
+/// `SELECT Industry, COUNT(Id) cnt, SUM(AnnualRevenue) FROM Account GROUP BY Industry`.
+/// Values are accessed by their alias, which is `expr0`, `expr1`, ... for
+/// unaliased aggregates
+#[derive(Deserialize, Debug)]
+pub struct AggregateResult {
+    #[serde(flatten)]
+    values: HashMap<String, serde_json::Value>,
+}
+
+impl AggregateResult {
+    /// Returns the value of the alias or group-by field
+    pub fn get<T: DeserializeOwned>(&self, alias: &str) -> Result<T, Error> {
+        let value = self
+            .values
+            .get(alias)
+            .ok_or_else(|| Error::DeserializeError(format!("{} is missing", alias)))?;
+        Ok(T::deserialize(value)?)
+    }
+
+    /// Returns the value of the nth unaliased aggregate
+    pub fn expr<T: DeserializeOwned>(&self, n: usize) -> Result<T, Error> {
+        self.get(&format!("expr{}", n))
+    }
+
+    pub fn count(&self, alias: &str) -> Result<i64, Error> {
+        self.get(alias)
+    }
+
+    pub fn sum(&self, alias: &str) -> Result<Option<f64>, Error> {
+        self.get(alias)
+    }
+
+    pub fn avg(&self, alias: &str) -> Result<Option<f64>, Error> {
+        self.get(alias)
+    }
+
+    pub fn min<T: DeserializeOwned>(&self, alias: &str) -> Result<Option<T>, Error> {
+        self.get(alias)
+    }
+
+    pub fn max<T: DeserializeOwned>(&self, alias: &str) -> Result<Option<T>, Error> {
+        self.get(alias)
+    }
+
+    /// Returns true when the row is a subtotal of the field of `GROUPING`
+    pub fn grouping(&self, alias: &str) -> Result<bool, Error> {
+        Ok(self.get::<i64>(alias)? == 1)
+    }
+}
+
+/// Response of the query `explain` parameter
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainResponse {
+    pub plans: Vec<QueryPlan>,
+    pub source_query: Option<String>,
+}
+
+/// A query plan, ordered from the cheapest in `ExplainResponse::plans`
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryPlan {
+    pub cardinality: u64,
+    #[serde(default)]
+    pub fields: Vec<String>,
+    pub leading_operation_type: String,
+    #[serde(default)]
+    pub notes: Vec<QueryPlanNote>,
+    pub relative_cost: f64,
+    pub sobject_cardinality: u64,
+    pub sobject_type: String,
+}
+
+impl QueryPlan {
+    /// Returns true when the plan is cheaper than a full table scan. A
+    /// relative cost above 1.0 means the query is not selective
+    pub fn is_selective(&self) -> bool {
+        self.relative_cost <= 1.0
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryPlanNote {
+    pub description: String,
+    #[serde(default)]
+    pub fields: Vec<String>,
+    pub table_enum_or_id: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct CreateResponse {
     pub id: String,
     pub success: bool,
 }
 
+/// Result of a record in an SObject Collections request
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveResult {
+    pub id: Option<String>,
+    pub success: bool,
+    #[serde(default)]
+    pub errors: Vec<SaveError>,
+    /// Whether an upsert created the record
+    pub created: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveError {
+    pub status_code: String,
+    pub message: String,
+    #[serde(default)]
+    pub fields: Vec<String>,
+}
+
+/// Response of `/composite`
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompositeResponse {
+    pub composite_response: Vec<CompositeSubresponse>,
+}
+
+impl CompositeResponse {
+    /// Returns the response of the subrequest with the reference ID
+    pub fn get(&self, reference_id: &str) -> Option<&CompositeSubresponse> {
+        self.composite_response
+            .iter()
+            .find(|r| r.reference_id == reference_id)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompositeSubresponse {
+    #[serde(default)]
+    pub body: serde_json::Value,
+    #[serde(default)]
+    pub http_headers: HashMap<String, String>,
+    pub http_status_code: u16,
+    pub reference_id: String,
+}
+
+impl CompositeSubresponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.http_status_code)
+    }
+
+    /// Deserializes the body of a successful subrequest, or returns the
+    /// errors of a failed one
+    pub fn body<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        if self.is_success() {
+            Ok(T::deserialize(&self.body)?)
+        } else {
+            Err(Error::ErrorResponses(Vec::<ErrorResponse>::deserialize(
+                &self.body,
+            )?))
+        }
+    }
+}
+
+/// Response of `/composite/graph`
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphResponse {
+    pub graphs: Vec<GraphResult>,
+}
+
+impl GraphResponse {
+    /// Returns the outcome of the graph with the ID
+    pub fn get(&self, graph_id: &str) -> Option<&GraphResult> {
+        self.graphs.iter().find(|g| g.graph_id == graph_id)
+    }
+}
+
+/// Outcome of a graph. When a node fails, the whole graph is rolled back
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphResult {
+    pub graph_id: String,
+    pub graph_response: CompositeResponse,
+    pub is_successful: bool,
+}
+
+/// Response of `/composite/batch`
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResponse {
+    pub has_errors: bool,
+    pub results: Vec<BatchResult>,
+}
+
+/// Result of a subrequest of a batch, in the order of the subrequests
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult {
+    pub status_code: u16,
+    #[serde(default)]
+    pub result: serde_json::Value,
+}
+
+impl BatchResult {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+
+    /// Deserializes the result of a successful subrequest, or returns the
+    /// errors of a failed one
+    pub fn result<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        if self.is_success() {
+            Ok(T::deserialize(&self.result)?)
+        } else {
+            Err(Error::ErrorResponses(Vec::<ErrorResponse>::deserialize(
+                &self.result,
+            )?))
+        }
+    }
+}
+
+/// Response of `/composite/tree`
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeResponse {
+    pub has_errors: bool,
+    pub results: Vec<TreeResult>,
+}
+
+impl TreeResponse {
+    /// Returns the IDs of the created records by reference ID
+    pub fn ids(&self) -> HashMap<&str, &str> {
+        self.results
+            .iter()
+            .filter_map(|r| r.id.as_deref().map(|id| (r.reference_id.as_str(), id)))
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeResult {
+    pub reference_id: String,
+    pub id: Option<String>,
+    #[serde(default)]
+    pub errors: Vec<SaveError>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct UpsertResponse {
-    create: Option<CreateResponse>,
+    pub create: Option<CreateResponse>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -33,24 +315,125 @@ pub struct ErrorResponse {
 #[derive(Deserialize, Debug)]
 pub struct TokenResponse {
     pub id: String,
+    #[serde(default)]
     pub issued_at: String,
     pub access_token: String,
+    pub refresh_token: Option<String>,
     pub instance_url: String,
+    #[serde(default)]
     pub signature: String,
     pub token_type: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct TokenErrorResponse {
-    error: String,
-    error_description: String,
+    pub error: String,
+    pub error_description: String,
 }
 
-#[derive(Debug)]
+#[derive(Deserialize, Debug)]
+pub struct IntrospectResponse {
+    pub active: bool,
+    pub scope: Option<String>,
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    pub sub: Option<String>,
+    pub token_type: Option<String>,
+    pub exp: Option<i64>,
+    pub iat: Option<i64>,
+    pub nbf: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UserInfoResponse {
+    pub sub: String,
+    pub user_id: String,
+    pub organization_id: String,
+    pub preferred_username: Option<String>,
+    pub nickname: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub zoneinfo: Option<String>,
+    pub active: Option<bool>,
+    pub user_type: Option<String>,
+    pub language: Option<String>,
+    pub locale: Option<String>,
+    #[serde(rename = "utcOffset")]
+    pub utc_offset: Option<i64>,
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub urls: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IdentityResponse {
+    pub id: String,
+    pub asserted_user: bool,
+    pub user_id: String,
+    pub organization_id: String,
+    pub username: String,
+    pub nick_name: Option<String>,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub timezone: Option<String>,
+    pub active: Option<bool>,
+    pub user_type: Option<String>,
+    pub language: Option<String>,
+    pub locale: Option<String>,
+    #[serde(rename = "utcOffset")]
+    pub utc_offset: Option<i64>,
+    pub last_modified_date: Option<String>,
+    #[serde(default)]
+    pub urls: HashMap<String, String>,
+}
+
+/// Result of the SOAP partner API `login` call
+#[derive(Debug, Clone)]
+pub struct LoginResult {
+    pub metadata_server_url: String,
+    pub password_expired: bool,
+    pub sandbox: bool,
+    pub server_url: String,
+    pub session_id: String,
+    pub user_id: String,
+    pub organization_id: String,
+    pub organization_name: Option<String>,
+    pub user_name: Option<String>,
+    pub user_email: Option<String>,
+    pub session_seconds_valid: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeviceCodeResponse {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub interval: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AccessToken {
     pub token_type: String,
     pub value: String,
     pub issued_at: String,
+    #[serde(default)]
+    pub expires_at: Option<SystemTime>,
+}
+
+impl AccessToken {
+    /// Returns true when the expiry of the session is known and has passed
+    pub fn is_expired(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at <= SystemTime::now(),
+            None => false,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -224,19 +607,73 @@ pub struct DescribeGlobalSObjectResponse {
     pub urls: HashMap<String, String>,
 }
 
+/// Response of SOSL and parameterized search. Use `serde_json::Value` as
+/// `T` to handle the records of every sobject type as raw JSON
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
-pub struct SearchResponse {
-    pub search_records: Vec<SearchRecord>,
-    //    pub metadata: Metadata,
+pub struct SearchResponse<T = SearchRecord> {
+    pub search_records: Vec<T>,
+    pub metadata: Option<SearchMetadata>,
 }
 
+/// A search hit with the returned fields other than `Id` kept untyped
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchRecord {
     #[serde(rename = "Id")]
     pub id: String,
     pub attributes: SObjectAttribute,
+    #[serde(flatten)]
+    pub fields: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMetadata {
+    pub spell_correction_applied: Option<bool>,
+    #[serde(default)]
+    pub entity_metadata: Vec<SearchEntityMetadata>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchEntityMetadata {
+    pub entity_name: String,
+    #[serde(default)]
+    pub field_metadata: Vec<SearchFieldMetadata>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFieldMetadata {
+    pub name: String,
+    pub label: String,
+}
+
+/// Response of search suggestions and title matches
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestionsResponse {
+    pub auto_suggest_results: Vec<SearchRecord>,
+    pub has_more_results: bool,
+}
+
+/// Search result layout of an sobject
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchLayoutResponse {
+    pub label: String,
+    pub limit_rows: u32,
+    pub search_columns: Vec<SearchColumn>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchColumn {
+    pub field: String,
+    pub format: Option<String>,
+    pub label: String,
+    pub name: String,
 }
 
 #[derive(Deserialize, Debug)]
@@ -0,0 +1,196 @@
+//! Request of the parameterized search endpoint
+//!
+//! ```
+//! use rustforce::search::{ParameterizedSearch, SearchSObject};
+//!
+//! let request = ParameterizedSearch::new("Acme")
+//!     .fields(&["Id", "Name"])
+//!     .sobject(SearchSObject::new("Account").filter("Industry = 'Banking'").limit(10))
+//!     .sobject(SearchSObject::new("Contact"))
+//!     .spell_correction(false);
+//! ```
+use serde::Serialize;
+
+/// Body of `/parameterizedSearch`
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ParameterizedSearch {
+    q: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sobjects: Vec<SearchSObject>,
+    #[serde(rename = "in", skip_serializing_if = "Option::is_none")]
+    search_in: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overall_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spell_correction: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    data_categories: Vec<DataCategoryFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<String>,
+}
+
+impl ParameterizedSearch {
+    /// Starts a search for the term. The term is sent as is, so reserved
+    /// characters need `utils::escape_sosl`
+    pub fn new(q: &str) -> Self {
+        ParameterizedSearch {
+            q: q.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Fields returned for every sobject without its own field list
+    pub fn fields(mut self, fields: &[&str]) -> Self {
+        self.fields = fields.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    pub fn sobject(mut self, sobject: SearchSObject) -> Self {
+        self.sobjects.push(sobject);
+        self
+    }
+
+    /// Scope of the fields to search such as `ALL`, `NAME`, `EMAIL`
+    pub fn search_in(mut self, scope: &str) -> Self {
+        self.search_in = Some(scope.to_string());
+        self
+    }
+
+    pub fn overall_limit(mut self, limit: u32) -> Self {
+        self.overall_limit = Some(limit);
+        self
+    }
+
+    pub fn default_limit(mut self, limit: u32) -> Self {
+        self.default_limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn spell_correction(mut self, spell_correction: bool) -> Self {
+        self.spell_correction = Some(spell_correction);
+        self
+    }
+
+    pub fn data_category(mut self, filter: DataCategoryFilter) -> Self {
+        self.data_categories.push(filter);
+        self
+    }
+
+    /// Returns the field labels in the metadata of the response
+    pub fn with_labels(mut self) -> Self {
+        self.metadata = Some("LABELS".to_string());
+        self
+    }
+}
+
+/// An sobject to search with its own fields, filter and limit
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSObject {
+    name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<String>,
+    #[serde(rename = "where", skip_serializing_if = "Option::is_none")]
+    filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    order_by: Option<String>,
+}
+
+impl SearchSObject {
+    pub fn new(name: &str) -> Self {
+        SearchSObject {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn fields(mut self, fields: &[&str]) -> Self {
+        self.fields = fields.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    /// Sets the SOQL `where` condition of the sobject
+    pub fn filter(mut self, condition: &str) -> Self {
+        self.filter = Some(condition.to_string());
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn order_by(mut self, order_by: &str) -> Self {
+        self.order_by = Some(order_by.to_string());
+        self
+    }
+}
+
+/// Filter of knowledge articles by data category
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DataCategoryFilter {
+    pub group_name: String,
+    /// `ABOVE`, `AT`, `BELOW` or `ABOVE_OR_BELOW`
+    pub operator: String,
+    pub categories: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataCategoryFilter, ParameterizedSearch, SearchSObject};
+    use serde_json::json;
+
+    #[test]
+    fn serialize() {
+        let request = ParameterizedSearch::new("Acme")
+            .fields(&["Id", "Name"])
+            .sobject(
+                SearchSObject::new("Account")
+                    .filter("Industry = 'Banking'")
+                    .limit(10),
+            )
+            .sobject(SearchSObject::new("Contact").fields(&["Id", "Email"]))
+            .search_in("NAME")
+            .overall_limit(50)
+            .spell_correction(false)
+            .data_category(DataCategoryFilter {
+                group_name: "Products".to_string(),
+                operator: "ABOVE".to_string(),
+                categories: vec!["Laptops".to_string()],
+            })
+            .with_labels();
+        assert_eq!(
+            json!({
+                "q": "Acme",
+                "fields": ["Id", "Name"],
+                "sobjects": [
+                    {"name": "Account", "where": "Industry = 'Banking'", "limit": 10},
+                    {"name": "Contact", "fields": ["Id", "Email"]},
+                ],
+                "in": "NAME",
+                "overallLimit": 50,
+                "spellCorrection": false,
+                "dataCategories": [
+                    {"groupName": "Products", "operator": "ABOVE", "categories": ["Laptops"]},
+                ],
+                "metadata": "LABELS",
+            }),
+            serde_json::to_value(&request).unwrap()
+        );
+    }
+}
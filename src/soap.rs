@@ -0,0 +1,184 @@
+//! Envelopes and response parsing for the SOAP partner API login and logout
+use crate::errors::Error;
+use crate::response::{ErrorResponse, LoginResult};
+use crate::utils::escape_xml;
+use roxmltree::{Document, Node};
+
+/// Builds the envelope of the partner API `login` call
+pub(crate) fn login_envelope(username: &str, password: &str) -> String {
+    [
+        "<se:Envelope xmlns:se='http://schemas.xmlsoap.org/soap/envelope/'>",
+        "<se:Header/>",
+        "<se:Body>",
+        "<login xmlns='urn:partner.soap.sforce.com'>",
+        format!("<username>{}</username>", escape_xml(username)).as_str(),
+        format!("<password>{}</password>", escape_xml(password)).as_str(),
+        "</login>",
+        "</se:Body>",
+        "</se:Envelope>",
+    ]
+    .join("")
+}
+
+/// Builds the envelope of the partner API `logout` call
+pub(crate) fn logout_envelope(session_id: &str) -> String {
+    [
+        "<se:Envelope xmlns:se='http://schemas.xmlsoap.org/soap/envelope/'>",
+        "<se:Header>",
+        "<SessionHeader xmlns='urn:partner.soap.sforce.com'>",
+        format!("<sessionId>{}</sessionId>", escape_xml(session_id)).as_str(),
+        "</SessionHeader>",
+        "</se:Header>",
+        "<se:Body>",
+        "<logout xmlns='urn:partner.soap.sforce.com'/>",
+        "</se:Body>",
+        "</se:Envelope>",
+    ]
+    .join("")
+}
+
+/// Parses the `loginResponse` envelope
+pub(crate) fn parse_login_response(body: &str) -> Result<LoginResult, Error> {
+    let doc = Document::parse(body).map_err(|e| Error::DeserializeError(e.to_string()))?;
+    let result = find(doc.root(), "result")?;
+    let user_info = find(result, "userInfo")?;
+    Ok(LoginResult {
+        metadata_server_url: text(result, "metadataServerUrl")?,
+        password_expired: boolean(result, "passwordExpired")?,
+        sandbox: boolean(result, "sandbox")?,
+        server_url: text(result, "serverUrl")?,
+        session_id: text(result, "sessionId")?,
+        user_id: text(result, "userId")?,
+        organization_id: text(user_info, "organizationId")?,
+        organization_name: optional_text(user_info, "organizationName"),
+        user_name: optional_text(user_info, "userName"),
+        user_email: optional_text(user_info, "userEmail"),
+        session_seconds_valid: text(user_info, "sessionSecondsValid")?
+            .parse()
+            .map_err(|_| Error::DeserializeError("invalid sessionSecondsValid".to_string()))?,
+    })
+}
+
+/// Converts a SOAP fault into `Error::LoginError`. Bodies that are not a
+/// fault give `Error::DeserializeError`
+pub(crate) fn parse_fault(body: &str) -> Error {
+    let doc = match Document::parse(body) {
+        Ok(doc) => doc,
+        Err(e) => return Error::DeserializeError(e.to_string()),
+    };
+    match (
+        optional_text(doc.root(), "faultcode"),
+        optional_text(doc.root(), "faultstring"),
+    ) {
+        (Some(error_code), Some(message)) => Error::LoginError(ErrorResponse {
+            message,
+            error_code,
+            fields: None,
+        }),
+        _ => Error::DeserializeError(format!("unexpected SOAP response {}", body)),
+    }
+}
+
+fn find<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Result<Node<'a, 'input>, Error> {
+    node.descendants()
+        .find(|n| n.has_tag_name(name))
+        .ok_or_else(|| Error::DeserializeError(format!("{} is missing", name)))
+}
+
+fn optional_text(node: Node, name: &str) -> Option<String> {
+    node.descendants()
+        .find(|n| n.has_tag_name(name))
+        .and_then(|n| n.text())
+        .map(|t| t.to_string())
+}
+
+fn text(node: Node, name: &str) -> Result<String, Error> {
+    optional_text(node, name).ok_or_else(|| Error::DeserializeError(format!("{} is missing", name)))
+}
+
+fn boolean(node: Node, name: &str) -> Result<bool, Error> {
+    match text(node, name)?.as_str() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        v => Err(Error::DeserializeError(format!("invalid {} {}", name, v))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{login_envelope, parse_fault, parse_login_response};
+    use crate::errors::Error;
+
+    const LOGIN_RESPONSE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<soapenv:Envelope xmlns:soapenv="http://schemas.xmlsoap.org/soap/envelope/" xmlns="urn:partner.soap.sforce.com" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <soapenv:Body>
+    <loginResponse>
+      <result>
+        <metadataServerUrl>https://ap.salesforce.com/services/Soap/m/44.0/00Dxx0000001gPL</metadataServerUrl>
+        <passwordExpired>false</passwordExpired>
+        <sandbox>true</sandbox>
+        <serverUrl>https://ap.salesforce.com/services/Soap/u/44.0/00Dxx0000001gPL</serverUrl>
+        <sessionId>00Dxx0000001gPL!this_is_session_id</sessionId>
+        <userId>005xx000001SwiU</userId>
+        <userInfo>
+          <organizationId>00Dxx0000001gPL</organizationId>
+          <organizationName>Example</organizationName>
+          <sessionSecondsValid>7200</sessionSecondsValid>
+          <userEmail>user@example.com</userEmail>
+          <userName>user@example.com</userName>
+        </userInfo>
+      </result>
+    </loginResponse>
+  </soapenv:Body>
+</soapenv:Envelope>"#;
+
+    #[test]
+    fn escape_credentials() {
+        let envelope = login_envelope("a&b@example.com", "p<ss>'\"");
+        assert!(envelope.contains("<username>a&amp;b@example.com</username>"));
+        assert!(envelope.contains("<password>p&lt;ss&gt;&apos;&quot;</password>"));
+    }
+
+    #[test]
+    fn login_response() -> Result<(), Error> {
+        let r = parse_login_response(LOGIN_RESPONSE)?;
+        assert_eq!("00Dxx0000001gPL!this_is_session_id", r.session_id);
+        assert_eq!("005xx000001SwiU", r.user_id);
+        assert_eq!("00Dxx0000001gPL", r.organization_id);
+        assert!(!r.password_expired);
+        assert!(r.sandbox);
+        assert_eq!(7200, r.session_seconds_valid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn unexpected_response() {
+        let r = parse_login_response("<html>Service Unavailable</html>");
+        assert!(matches!(r, Err(Error::DeserializeError(_))));
+        assert!(matches!(
+            parse_fault("Service Unavailable"),
+            Error::DeserializeError(_)
+        ));
+    }
+
+    #[test]
+    fn fault() {
+        let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+<soapenv:Envelope xmlns:soapenv="http://schemas.xmlsoap.org/soap/envelope/" xmlns:sf="urn:fault.partner.soap.sforce.com">
+  <soapenv:Body>
+    <soapenv:Fault>
+      <faultcode>INVALID_LOGIN</faultcode>
+      <faultstring>INVALID_LOGIN: Invalid username, password, security token; or user locked out.</faultstring>
+    </soapenv:Fault>
+  </soapenv:Body>
+</soapenv:Envelope>"#;
+        match parse_fault(body) {
+            Error::LoginError(e) => {
+                assert_eq!("INVALID_LOGIN", e.error_code);
+                assert!(e.message.starts_with("INVALID_LOGIN: Invalid username"));
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+}
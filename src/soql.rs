@@ -0,0 +1,716 @@
+//! Builder for SOQL queries
+//!
+//! Values are rendered as escaped SOQL literals, so user input can be passed
+//! to conditions without being interpolated into the query text by hand.
+//!
+//! ```
+//! use rustforce::soql::{Condition, Order, Query};
+//!
+//! let query = Query::select(&["Id", "Name", "Owner.Name"])
+//!     .subquery(Query::select(&["Id", "Email"]).from("Contacts"))
+//!     .from("Account")
+//!     .filter(Condition::and(vec![
+//!         Condition::eq("Industry", "Banking"),
+//!         Condition::like("Name", "O'Reilly%"),
+//!     ]))
+//!     .order_by("Name", Order::Asc)
+//!     .limit(10);
+//! assert_eq!(
+//!     "SELECT Id, Name, Owner.Name, (SELECT Id, Email FROM Contacts) FROM Account \
+//!      WHERE (Industry = 'Banking' AND Name LIKE 'O\\'Reilly%') ORDER BY Name ASC LIMIT 10",
+//!     query.to_soql().unwrap()
+//! );
+//! ```
+use crate::errors::Error;
+use crate::utils::{
+    escape_like, escape_soql, format_soql_date, format_soql_datetime, RelativeDate,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// A literal value in a condition
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Date(NaiveDate),
+    DateTime(DateTime<Utc>),
+    RelativeDate(RelativeDate),
+}
+
+impl Value {
+    /// Renders the value as a SOQL literal. NaN and infinite floats have no
+    /// literal and are rejected
+    pub fn to_soql(&self) -> Result<String, Error> {
+        Ok(match self {
+            Value::Null => "null".to_string(),
+            Value::Bool(v) => v.to_string(),
+            Value::Int(v) => v.to_string(),
+            Value::Float(v) if !v.is_finite() => {
+                return Err(Error::InvalidRequest(format!("{} is not a SOQL number", v)))
+            }
+            Value::Float(v) => v.to_string(),
+            Value::String(v) => format!("'{}'", escape_soql(v)),
+            Value::Date(v) => format_soql_date(v),
+            Value::DateTime(v) => format_soql_datetime(v),
+            Value::RelativeDate(v) => v.to_string(),
+        })
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::String(v)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Self {
+        Value::Int(v.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::Int(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl From<NaiveDate> for Value {
+    fn from(v: NaiveDate) -> Self {
+        Value::Date(v)
+    }
+}
+
+impl From<DateTime<Utc>> for Value {
+    fn from(v: DateTime<Utc>) -> Self {
+        Value::DateTime(v)
+    }
+}
+
+impl From<RelativeDate> for Value {
+    fn from(v: RelativeDate) -> Self {
+        Value::RelativeDate(v)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Operator {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Eq => "=",
+            Operator::Ne => "!=",
+            Operator::Lt => "<",
+            Operator::Le => "<=",
+            Operator::Gt => ">",
+            Operator::Ge => ">=",
+        }
+    }
+}
+
+/// A condition of a WHERE or HAVING clause
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare {
+        field: String,
+        operator: Operator,
+        value: Value,
+    },
+    In {
+        field: String,
+        values: Vec<Value>,
+        negate: bool,
+    },
+    Like {
+        field: String,
+        pattern: LikePattern,
+        negate: bool,
+    },
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+/// An escaped LIKE pattern, built only by the `Condition` constructors so
+/// that it can be rendered without escaping it again
+#[derive(Debug, Clone, PartialEq)]
+pub struct LikePattern(String);
+
+impl Condition {
+    pub fn compare<V: Into<Value>>(field: &str, operator: Operator, value: V) -> Self {
+        Condition::Compare {
+            field: field.to_string(),
+            operator,
+            value: value.into(),
+        }
+    }
+
+    pub fn eq<V: Into<Value>>(field: &str, value: V) -> Self {
+        Self::compare(field, Operator::Eq, value)
+    }
+
+    pub fn ne<V: Into<Value>>(field: &str, value: V) -> Self {
+        Self::compare(field, Operator::Ne, value)
+    }
+
+    pub fn lt<V: Into<Value>>(field: &str, value: V) -> Self {
+        Self::compare(field, Operator::Lt, value)
+    }
+
+    pub fn le<V: Into<Value>>(field: &str, value: V) -> Self {
+        Self::compare(field, Operator::Le, value)
+    }
+
+    pub fn gt<V: Into<Value>>(field: &str, value: V) -> Self {
+        Self::compare(field, Operator::Gt, value)
+    }
+
+    pub fn ge<V: Into<Value>>(field: &str, value: V) -> Self {
+        Self::compare(field, Operator::Ge, value)
+    }
+
+    pub fn is_in<V: Into<Value>, I: IntoIterator<Item = V>>(field: &str, values: I) -> Self {
+        Condition::In {
+            field: field.to_string(),
+            values: values.into_iter().map(Into::into).collect(),
+            negate: false,
+        }
+    }
+
+    pub fn not_in<V: Into<Value>, I: IntoIterator<Item = V>>(field: &str, values: I) -> Self {
+        Condition::In {
+            field: field.to_string(),
+            values: values.into_iter().map(Into::into).collect(),
+            negate: true,
+        }
+    }
+
+    /// `%` and `_` in the pattern are wildcards. Quotes and backslashes are
+    /// escaped. To match user input literally, use `starts_with`, `ends_with`
+    /// or `contains` instead
+    pub fn like(field: &str, pattern: &str) -> Self {
+        Self::like_pattern(field, escape_soql(pattern), false)
+    }
+
+    pub fn not_like(field: &str, pattern: &str) -> Self {
+        Self::like_pattern(field, escape_soql(pattern), true)
+    }
+
+    /// Matches values starting with `value`, taken literally
+    pub fn starts_with(field: &str, value: &str) -> Self {
+        Self::like_pattern(field, format!("{}%", escape_like(value)), false)
+    }
+
+    pub fn not_starts_with(field: &str, value: &str) -> Self {
+        Self::like_pattern(field, format!("{}%", escape_like(value)), true)
+    }
+
+    /// Matches values ending with `value`, taken literally
+    pub fn ends_with(field: &str, value: &str) -> Self {
+        Self::like_pattern(field, format!("%{}", escape_like(value)), false)
+    }
+
+    pub fn not_ends_with(field: &str, value: &str) -> Self {
+        Self::like_pattern(field, format!("%{}", escape_like(value)), true)
+    }
+
+    /// Matches values containing `value`, taken literally
+    pub fn contains(field: &str, value: &str) -> Self {
+        Self::like_pattern(field, format!("%{}%", escape_like(value)), false)
+    }
+
+    pub fn not_contains(field: &str, value: &str) -> Self {
+        Self::like_pattern(field, format!("%{}%", escape_like(value)), true)
+    }
+
+    fn like_pattern(field: &str, escaped: String, negate: bool) -> Self {
+        Condition::Like {
+            field: field.to_string(),
+            pattern: LikePattern(escaped),
+            negate,
+        }
+    }
+
+    pub fn and(conditions: Vec<Condition>) -> Self {
+        Condition::And(conditions)
+    }
+
+    pub fn or(conditions: Vec<Condition>) -> Self {
+        Condition::Or(conditions)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(condition: Condition) -> Self {
+        Condition::Not(Box::new(condition))
+    }
+
+    /// Renders the condition as SOQL. Empty `IN` lists and empty `AND` or
+    /// `OR` groups have no valid SOQL and are rejected
+    pub fn to_soql(&self) -> Result<String, Error> {
+        Ok(match self {
+            Condition::Compare {
+                field,
+                operator,
+                value,
+            } => format!("{} {} {}", field, operator.as_str(), value.to_soql()?),
+            Condition::In { field, values, .. } if values.is_empty() => {
+                return Err(Error::InvalidRequest(format!(
+                    "no values to match {} against",
+                    field
+                )))
+            }
+            Condition::In {
+                field,
+                values,
+                negate,
+            } => format!(
+                "{} {}IN ({})",
+                field,
+                if *negate { "NOT " } else { "" },
+                values
+                    .iter()
+                    .map(Value::to_soql)
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(", ")
+            ),
+            Condition::Like {
+                field,
+                pattern,
+                negate,
+            } => {
+                let like = format!("{} LIKE '{}'", field, pattern.0);
+                if *negate {
+                    format!("(NOT {})", like)
+                } else {
+                    like
+                }
+            }
+            Condition::And(conditions) => join_conditions(conditions, " AND ")?,
+            Condition::Or(conditions) => join_conditions(conditions, " OR ")?,
+            Condition::Not(condition) => format!("(NOT {})", condition.to_soql()?),
+        })
+    }
+}
+
+fn join_conditions(conditions: &[Condition], separator: &str) -> Result<String, Error> {
+    if conditions.is_empty() {
+        return Err(Error::InvalidRequest(format!(
+            "no conditions to join with{}",
+            separator.trim_end()
+        )));
+    }
+    Ok(format!(
+        "({})",
+        conditions
+            .iter()
+            .map(Condition::to_soql)
+            .collect::<Result<Vec<_>, _>>()?
+            .join(separator)
+    ))
+}
+
+/// Sort direction of ORDER BY
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// Position of null values in ORDER BY
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Nulls {
+    First,
+    Last,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct OrderBy {
+    field: String,
+    order: Order,
+    nulls: Option<Nulls>,
+}
+
+/// A SOQL query. Child relationship subqueries are built with the same type
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Query {
+    fields: Vec<String>,
+    subqueries: Vec<Query>,
+    from: String,
+    filter: Option<Condition>,
+    group_by: Vec<String>,
+    having: Option<Condition>,
+    order_by: Vec<OrderBy>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    for_update: bool,
+}
+
+impl Query {
+    /// Starts a query selecting the fields. Parent relationship fields are
+    /// selected with dot notation such as `Owner.Name`
+    pub fn select(fields: &[&str]) -> Self {
+        Query {
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn field(mut self, field: &str) -> Self {
+        self.fields.push(field.to_string());
+        self
+    }
+
+    /// Adds a child relationship subquery. Its `from` is the relationship name
+    pub fn subquery(mut self, query: Query) -> Self {
+        self.subqueries.push(query);
+        self
+    }
+
+    pub fn from(mut self, sobject: &str) -> Self {
+        self.from = sobject.to_string();
+        self
+    }
+
+    /// Sets the WHERE clause
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.filter = Some(condition);
+        self
+    }
+
+    pub fn group_by(mut self, fields: &[&str]) -> Self {
+        self.group_by = fields.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    pub fn having(mut self, condition: Condition) -> Self {
+        self.having = Some(condition);
+        self
+    }
+
+    pub fn order_by(mut self, field: &str, order: Order) -> Self {
+        self.order_by.push(OrderBy {
+            field: field.to_string(),
+            order,
+            nulls: None,
+        });
+        self
+    }
+
+    pub fn order_by_nulls(mut self, field: &str, order: Order, nulls: Nulls) -> Self {
+        self.order_by.push(OrderBy {
+            field: field.to_string(),
+            order,
+            nulls: Some(nulls),
+        });
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn for_update(mut self) -> Self {
+        self.for_update = true;
+        self
+    }
+
+    /// Renders the query as SOQL to pass to `Client::query`. Fails when
+    /// nothing is selected, `from` is missing or a condition has no valid SOQL
+    pub fn to_soql(&self) -> Result<String, Error> {
+        if self.from.is_empty() {
+            return Err(Error::InvalidRequest(
+                "no sobject to select from".to_string(),
+            ));
+        }
+        let mut select: Vec<String> = self.fields.clone();
+        for subquery in &self.subqueries {
+            select.push(format!("({})", subquery.to_soql()?));
+        }
+        if select.is_empty() {
+            return Err(Error::InvalidRequest(format!(
+                "no fields to select from {}",
+                self.from
+            )));
+        }
+        let mut soql = format!("SELECT {} FROM {}", select.join(", "), self.from);
+        if let Some(filter) = self.filter.as_ref() {
+            soql.push_str(&format!(" WHERE {}", filter.to_soql()?));
+        }
+        if !self.group_by.is_empty() {
+            soql.push_str(&format!(" GROUP BY {}", self.group_by.join(", ")));
+        }
+        if let Some(having) = self.having.as_ref() {
+            soql.push_str(&format!(" HAVING {}", having.to_soql()?));
+        }
+        if !self.order_by.is_empty() {
+            let order_by: Vec<String> = self
+                .order_by
+                .iter()
+                .map(|o| {
+                    let mut s = format!(
+                        "{} {}",
+                        o.field,
+                        match o.order {
+                            Order::Asc => "ASC",
+                            Order::Desc => "DESC",
+                        }
+                    );
+                    match o.nulls {
+                        Some(Nulls::First) => s.push_str(" NULLS FIRST"),
+                        Some(Nulls::Last) => s.push_str(" NULLS LAST"),
+                        None => {}
+                    }
+                    s
+                })
+                .collect();
+            soql.push_str(&format!(" ORDER BY {}", order_by.join(", ")));
+        }
+        if let Some(limit) = self.limit {
+            soql.push_str(&format!(" LIMIT {}", limit));
+        }
+        if let Some(offset) = self.offset {
+            soql.push_str(&format!(" OFFSET {}", offset));
+        }
+        if self.for_update {
+            soql.push_str(" FOR UPDATE");
+        }
+        Ok(soql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Condition, Nulls, Order, Query, Value};
+    use crate::errors::Error;
+    use crate::utils::RelativeDate;
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    #[test]
+    fn select() -> Result<(), Error> {
+        let q = Query::select(&["Id", "Name"]).from("Account");
+        assert_eq!("SELECT Id, Name FROM Account", q.to_soql()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn escape_values() -> Result<(), Error> {
+        let q = Query::select(&["Id"])
+            .from("Contact")
+            .filter(Condition::eq("LastName", "O'Brien\\' OR Id != null"));
+        assert_eq!(
+            r"SELECT Id FROM Contact WHERE LastName = 'O\'Brien\\\' OR Id != null'",
+            q.to_soql()?
+        );
+        assert_eq!(r#"'a\nb\tc\"d'"#, Value::from("a\nb\tc\"d").to_soql()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn match_literally() -> Result<(), Error> {
+        let input = "O'Brien 100%_";
+        assert_eq!(
+            r"Name LIKE 'O\'Brien 100\%\_%'",
+            Condition::starts_with("Name", input).to_soql()?
+        );
+        assert_eq!(
+            r"Name LIKE '%O\'Brien 100\%\_'",
+            Condition::ends_with("Name", input).to_soql()?
+        );
+        assert_eq!(
+            r"Name LIKE '%O\'Brien 100\%\_%'",
+            Condition::contains("Name", input).to_soql()?
+        );
+        assert_eq!(
+            r"Name LIKE 'O\'Brien 100%_'",
+            Condition::like("Name", input).to_soql()?
+        );
+        assert_eq!(
+            r"(NOT Name LIKE 'O\'Brien 100\%\_%')",
+            Condition::not_starts_with("Name", input).to_soql()?
+        );
+        assert_eq!(
+            r"(NOT Name LIKE '%O\'Brien 100\%\_')",
+            Condition::not_ends_with("Name", input).to_soql()?
+        );
+        assert_eq!(
+            r"(NOT Name LIKE '%O\'Brien 100\%\_%')",
+            Condition::not_contains("Name", input).to_soql()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn typed_literals() -> Result<(), Error> {
+        let date = NaiveDate::from_ymd_opt(2020, 1, 31).unwrap();
+        let datetime = Utc.with_ymd_and_hms(2020, 1, 31, 9, 30, 0).unwrap();
+        let q = Query::select(&["Id"])
+            .from("Opportunity")
+            .filter(Condition::and(vec![
+                Condition::ge("CloseDate", date),
+                Condition::lt("CreatedDate", datetime),
+                Condition::gt("Amount", 1000.5),
+                Condition::eq("IsWon", true),
+                Condition::ne("AccountId", Option::<&str>::None),
+                Condition::is_in("StageName", vec!["Prospecting", "Closed Won"]),
+                Condition::not_in("Probability", vec![0, 100]),
+                Condition::eq("LastModifiedDate", RelativeDate::LastNDays(7)),
+            ]));
+        assert_eq!(
+            "SELECT Id FROM Opportunity WHERE (CloseDate >= 2020-01-31 \
+             AND CreatedDate < 2020-01-31T09:30:00Z AND Amount > 1000.5 AND IsWon = true \
+             AND AccountId != null AND StageName IN ('Prospecting', 'Closed Won') \
+             AND Probability NOT IN (0, 100) AND LastModifiedDate = LAST_N_DAYS:7)",
+            q.to_soql()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn nested_conditions() -> Result<(), Error> {
+        let q = Query::select(&["Id"])
+            .from("Account")
+            .filter(Condition::or(vec![
+                Condition::like("Name", "Acme%"),
+                Condition::not(Condition::and(vec![
+                    Condition::eq("Type", "Partner"),
+                    Condition::not_like("BillingCity", "San%"),
+                ])),
+            ]));
+        assert_eq!(
+            "SELECT Id FROM Account WHERE (Name LIKE 'Acme%' OR \
+             (NOT (Type = 'Partner' AND (NOT BillingCity LIKE 'San%'))))",
+            q.to_soql()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn relationships() -> Result<(), Error> {
+        let q = Query::select(&["Id", "Owner.Name"])
+            .subquery(
+                Query::select(&["Id", "Email"])
+                    .from("Contacts")
+                    .filter(Condition::eq("HasOptedOutOfEmail", false))
+                    .limit(5),
+            )
+            .from("Account");
+        assert_eq!(
+            "SELECT Id, Owner.Name, (SELECT Id, Email FROM Contacts \
+             WHERE HasOptedOutOfEmail = false LIMIT 5) FROM Account",
+            q.to_soql()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn clauses() -> Result<(), Error> {
+        let q = Query::select(&["Industry", "COUNT(Id)"])
+            .from("Account")
+            .group_by(&["Industry"])
+            .having(Condition::gt("COUNT(Id)", 1))
+            .order_by("Industry", Order::Asc)
+            .order_by_nulls("COUNT(Id)", Order::Desc, Nulls::Last)
+            .limit(10)
+            .offset(20);
+        assert_eq!(
+            "SELECT Industry, COUNT(Id) FROM Account GROUP BY Industry HAVING COUNT(Id) > 1 \
+             ORDER BY Industry ASC, COUNT(Id) DESC NULLS LAST LIMIT 10 OFFSET 20",
+            q.to_soql()?
+        );
+
+        let q = Query::select(&["Id"])
+            .field("Name")
+            .from("Account")
+            .filter(Condition::eq("Id", "001xx000003DGb2AAG"))
+            .for_update();
+        assert_eq!(
+            "SELECT Id, Name FROM Account WHERE Id = '001xx000003DGb2AAG' FOR UPDATE",
+            q.to_soql()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject_invalid() {
+        let empty: Vec<&str> = vec![];
+        for condition in [
+            Condition::is_in("Id", empty.clone()),
+            Condition::not_in("Id", empty),
+            Condition::and(vec![]),
+            Condition::or(vec![]),
+            Condition::not(Condition::or(vec![])),
+            Condition::gt("Amount", f64::NAN),
+            Condition::lt("Amount", f64::INFINITY),
+            Condition::is_in("Amount", vec![1.0, f64::NEG_INFINITY]),
+        ] {
+            assert!(matches!(condition.to_soql(), Err(Error::InvalidRequest(_))));
+            let q = Query::select(&["Id"]).from("Account").filter(condition);
+            assert!(matches!(q.to_soql(), Err(Error::InvalidRequest(_))));
+        }
+
+        let q = Query::select(&["Id"])
+            .subquery(
+                Query::select(&["Id"])
+                    .from("Contacts")
+                    .filter(Condition::and(vec![])),
+            )
+            .from("Account");
+        assert!(matches!(q.to_soql(), Err(Error::InvalidRequest(_))));
+
+        let q = Query::select(&[]).from("Account");
+        assert!(matches!(q.to_soql(), Err(Error::InvalidRequest(_))));
+        let q = Query::select(&["Id"]);
+        assert!(matches!(q.to_soql(), Err(Error::InvalidRequest(_))));
+        let q = Query::select(&["Id"]).from("");
+        assert!(matches!(q.to_soql(), Err(Error::InvalidRequest(_))));
+    }
+}
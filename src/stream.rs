@@ -0,0 +1,229 @@
+//! Record streams over paginated query results
+use crate::client::Client;
+use crate::errors::Error;
+use crate::response::QueryResponse;
+use futures::Stream;
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+type PageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<QueryResponse<T>, Error>> + Send + 'a>>;
+
+/// Stream of the records of a query. The next batch is fetched from
+/// `nextRecordsUrl` only when the records of the current batch are consumed,
+/// unless prefetching is enabled
+pub struct QueryStream<'a, T> {
+    client: &'a Client,
+    records: VecDeque<T>,
+    pending: Option<PageFuture<'a, T>>,
+    prefetched: Option<Result<QueryResponse<T>, Error>>,
+    next_records_url: Option<String>,
+    prefetch: bool,
+}
+
+// Records are never pinned, so the stream can be moved freely
+impl<T> Unpin for QueryStream<'_, T> {}
+
+impl<'a, T: DeserializeOwned + Send + 'a> QueryStream<'a, T> {
+    pub(crate) fn new(client: &'a Client, first_page: PageFuture<'a, T>) -> Self {
+        QueryStream {
+            client,
+            records: VecDeque::new(),
+            pending: Some(first_page),
+            prefetched: None,
+            next_records_url: None,
+            prefetch: false,
+        }
+    }
+
+    /// Starts fetching the next batch while the records of the current batch
+    /// are consumed. At most one batch is fetched ahead
+    pub fn prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    fn fetch_next(&mut self) -> bool {
+        match self.next_records_url.take() {
+            Some(next_records_url) => {
+                let client = self.client;
+                self.pending = Some(Box::pin(async move {
+                    client.query_more(&next_records_url).await
+                }));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn receive(&mut self, page: Result<QueryResponse<T>, Error>) -> Result<(), Error> {
+        let page = page?;
+        self.records.extend(page.records);
+        self.next_records_url = if page.done {
+            None
+        } else {
+            page.next_records_url
+        };
+        Ok(())
+    }
+}
+
+impl<'a, T: DeserializeOwned + Send + 'a> Stream for QueryStream<'a, T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(record) = this.records.pop_front() {
+                if this.prefetch && this.prefetched.is_none() {
+                    if this.pending.is_none() {
+                        this.fetch_next();
+                    }
+                    if let Some(pending) = this.pending.as_mut() {
+                        if let Poll::Ready(page) = pending.as_mut().poll(cx) {
+                            this.pending = None;
+                            this.prefetched = Some(page);
+                        }
+                    }
+                }
+                return Poll::Ready(Some(Ok(record)));
+            }
+
+            let page = match this.prefetched.take() {
+                Some(page) => page,
+                None => {
+                    if this.pending.is_none() && !this.fetch_next() {
+                        return Poll::Ready(None);
+                    }
+                    match this.pending.as_mut().unwrap().as_mut().poll(cx) {
+                        Poll::Ready(page) => {
+                            this.pending = None;
+                            page
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+            };
+            if let Err(e) = this.receive(page) {
+                this.next_records_url = None;
+                return Poll::Ready(Some(Err(e)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::Error;
+    use futures::TryStreamExt;
+    use mockito::mock;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Account {
+        id: String,
+    }
+
+    fn create_test_client() -> crate::Client {
+        let mut client = crate::Client::new(None, None);
+        client.set_instance_url(&mockito::server_url());
+        client.set_access_token("this_is_access_token").unwrap();
+        client
+    }
+
+    fn mock_pages(path: &str, query: &str) -> Vec<mockito::Mock> {
+        vec![
+            mock("GET", format!("{}?q={}", path, query).as_str())
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(
+                    json!({
+                        "totalSize": 3,
+                        "done": false,
+                        "nextRecordsUrl": "/services/data/v44.0/query/01gxx000000MbZr-2",
+                        "records": [{"Id": "1"}, {"Id": "2"}],
+                    })
+                    .to_string(),
+                )
+                .create(),
+            mock("GET", "/services/data/v44.0/query/01gxx000000MbZr-2")
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(
+                    json!({
+                        "totalSize": 3,
+                        "done": true,
+                        "records": [{"Id": "3"}],
+                    })
+                    .to_string(),
+                )
+                .expect(1)
+                .create(),
+        ]
+    }
+
+    #[tokio::test]
+    async fn query_stream() -> Result<(), Error> {
+        let m = mock_pages("/services/data/v44.0/query/", "SELECT+Id+FROM+Account");
+
+        let client = create_test_client();
+        let records: Vec<Account> = client
+            .query_stream("SELECT Id FROM Account")
+            .try_collect()
+            .await?;
+        assert_eq!(
+            vec!["1", "2", "3"],
+            records.iter().map(|r| r.id.as_str()).collect::<Vec<_>>()
+        );
+        m[1].assert();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_all_stream_with_prefetch() -> Result<(), Error> {
+        let m = mock_pages("/services/data/v44.0/queryAll/", "SELECT+Id+FROM+Account");
+
+        let client = create_test_client();
+        let records: Vec<Account> = client
+            .query_all_stream("SELECT Id FROM Account")
+            .prefetch(true)
+            .try_collect()
+            .await?;
+        assert_eq!(3, records.len());
+        m[1].assert();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_stream_error() -> Result<(), Error> {
+        let _m = mock(
+            "GET",
+            "/services/data/v44.0/query/?q=SELECT+Foo+FROM+Account",
+        )
+        .with_status(400)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!([{
+                "message": "No such column 'Foo' on entity 'Account'",
+                "errorCode": "INVALID_FIELD",
+            }])
+            .to_string(),
+        )
+        .create();
+
+        let client = create_test_client();
+        let r: Result<Vec<Account>, Error> = client
+            .query_stream("SELECT Foo FROM Account")
+            .try_collect()
+            .await;
+        assert!(matches!(r, Err(Error::ErrorResponses(_))));
+
+        Ok(())
+    }
+}
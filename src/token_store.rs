@@ -0,0 +1,209 @@
+//! Storage for the tokens obtained by `Client`
+//!
+//! The client reads the access token from its `TokenStore` on every request
+//! and writes to it after each login or refresh, so several clients (or
+//! processes, with `FileTokenStore`) can share one session instead of logging
+//! in separately.
+use crate::errors::Error;
+use crate::response::AccessToken;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{PoisonError, RwLock};
+
+/// Makes the temporary file of each `FileTokenStore::set` unique within the process
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Token and session information persisted by a `TokenStore`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredToken {
+    pub access_token: AccessToken,
+    pub instance_url: Option<String>,
+    pub refresh_token: Option<String>,
+}
+
+/// Provides and persists the tokens used by `Client`
+pub trait TokenStore: Send + Sync {
+    /// Returns the current token, if any
+    fn get(&self) -> Result<Option<StoredToken>, Error>;
+
+    /// Replaces the current token
+    fn set(&self, token: StoredToken) -> Result<(), Error>;
+
+    /// Removes the current token
+    fn clear(&self) -> Result<(), Error>;
+}
+
+/// Keeps the token in memory. Share it between clients with an `Arc`
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    token: RwLock<Option<StoredToken>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn get(&self) -> Result<Option<StoredToken>, Error> {
+        Ok(self
+            .token
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone())
+    }
+
+    fn set(&self, token: StoredToken) -> Result<(), Error> {
+        *self.token.write().unwrap_or_else(PoisonError::into_inner) = Some(token);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), Error> {
+        *self.token.write().unwrap_or_else(PoisonError::into_inner) = None;
+        Ok(())
+    }
+}
+
+/// Keeps the token in a JSON file so it survives restarts and can be shared
+/// between processes. The file is re-read on every `get`, so a token rotated
+/// by another process is picked up on the next request
+#[derive(Debug)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        FileTokenStore {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn get(&self) -> Result<Option<StoredToken>, Error> {
+        match fs::read(&self.path) {
+            Ok(body) => Ok(Some(serde_json::from_slice(&body)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::TokenStoreError(e.to_string())),
+        }
+    }
+
+    fn set(&self, token: StoredToken) -> Result<(), Error> {
+        // Write to a temporary file first so readers never see a partial token.
+        // The name is unique per write so concurrent writers don't clobber
+        // each other's temporary file
+        let mut tmp_name = self
+            .path
+            .file_name()
+            .map(OsString::from)
+            .unwrap_or_default();
+        tmp_name.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tmp_path = self.path.with_file_name(tmp_name);
+        write_private(&tmp_path, &serde_json::to_vec(&token)?)
+            .and_then(|()| fs::rename(&tmp_path, &self.path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp_path);
+                Error::TokenStoreError(e.to_string())
+            })
+    }
+
+    fn clear(&self) -> Result<(), Error> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Error::TokenStoreError(e.to_string())),
+        }
+    }
+}
+
+/// Creates the file readable and writable by the owner only, as it holds
+/// credentials
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(path)?.write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileTokenStore, MemoryTokenStore, StoredToken, TokenStore};
+    use crate::errors::Error;
+    use crate::response::AccessToken;
+    use std::env;
+
+    fn token() -> StoredToken {
+        StoredToken {
+            access_token: AccessToken {
+                token_type: "Bearer".to_string(),
+                value: "this_is_access_token".to_string(),
+                issued_at: "1569888000000".to_string(),
+                expires_at: None,
+            },
+            instance_url: Some("https://ap.salesforce.com".to_string()),
+            refresh_token: Some("this_is_refresh_token".to_string()),
+        }
+    }
+
+    #[test]
+    fn memory_token_store() -> Result<(), Error> {
+        let store = MemoryTokenStore::new();
+        assert!(store.get()?.is_none());
+        store.set(token())?;
+        assert_eq!(
+            "this_is_access_token",
+            store.get()?.unwrap().access_token.value
+        );
+        store.clear()?;
+        assert!(store.get()?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_token_store() -> Result<(), Error> {
+        let path = env::temp_dir().join(format!("rustforce-token-{}.json", std::process::id()));
+        let store = FileTokenStore::new(&path);
+        store.clear()?;
+        assert!(store.get()?.is_none());
+        store.set(token())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+
+        let other = FileTokenStore::new(&path);
+        let stored = other.get()?.unwrap();
+        assert_eq!("this_is_access_token", stored.access_token.value);
+        assert_eq!("1569888000000", stored.access_token.issued_at);
+        assert_eq!(
+            Some("https://ap.salesforce.com".to_string()),
+            stored.instance_url
+        );
+        assert_eq!(
+            Some("this_is_refresh_token".to_string()),
+            stored.refresh_token
+        );
+
+        store.clear()?;
+        assert!(other.get()?.is_none());
+
+        Ok(())
+    }
+}
@@ -1,6 +1,252 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use std::fmt;
+
 pub fn substring_before(body: &str, separator: &str) -> String {
     match body.find(separator) {
         Some(i) => body.get(..i).unwrap().to_string(),
         None => body.to_string(),
     }
 }
+
+/// Escapes the characters that are not allowed in XML text and attributes
+pub fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes a value to be placed between single quotes in SOQL or SOSL
+pub fn escape_soql(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\'' => escaped.push_str("\\'"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes a value to be matched literally in a LIKE pattern, including the
+/// `%` and `_` wildcards
+pub fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in escape_soql(value).chars() {
+        match c {
+            '%' => escaped.push_str("\\%"),
+            '_' => escaped.push_str("\\_"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes the reserved characters of a SOSL search term
+pub fn escape_sosl(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '?' | '&' | '|' | '!' | '{' | '}' | '[' | ']' | '(' | ')' | '^' | '~' | '*' | ':'
+            | '\\' | '"' | '\'' | '+' | '-' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats a date as a SOQL date literal such as `2020-01-31`
+pub fn format_soql_date(date: &NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Formats a datetime as a SOQL datetime literal in UTC such as
+/// `2020-01-31T09:30:00Z`
+pub fn format_soql_datetime<Tz: TimeZone>(datetime: &DateTime<Tz>) -> String {
+    datetime
+        .with_timezone(&Utc)
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
+}
+
+/// Relative date literals of SOQL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelativeDate {
+    Yesterday,
+    Today,
+    Tomorrow,
+    LastWeek,
+    ThisWeek,
+    NextWeek,
+    LastMonth,
+    ThisMonth,
+    NextMonth,
+    Last90Days,
+    Next90Days,
+    LastNDays(u32),
+    NextNDays(u32),
+    NDaysAgo(u32),
+    LastNWeeks(u32),
+    NextNWeeks(u32),
+    NWeeksAgo(u32),
+    LastNMonths(u32),
+    NextNMonths(u32),
+    NMonthsAgo(u32),
+    ThisQuarter,
+    LastQuarter,
+    NextQuarter,
+    LastNQuarters(u32),
+    NextNQuarters(u32),
+    NQuartersAgo(u32),
+    ThisYear,
+    LastYear,
+    NextYear,
+    LastNYears(u32),
+    NextNYears(u32),
+    NYearsAgo(u32),
+    ThisFiscalQuarter,
+    LastFiscalQuarter,
+    NextFiscalQuarter,
+    LastNFiscalQuarters(u32),
+    NextNFiscalQuarters(u32),
+    NFiscalQuartersAgo(u32),
+    ThisFiscalYear,
+    LastFiscalYear,
+    NextFiscalYear,
+    LastNFiscalYears(u32),
+    NextNFiscalYears(u32),
+    NFiscalYearsAgo(u32),
+}
+
+impl fmt::Display for RelativeDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RelativeDate::Yesterday => write!(f, "YESTERDAY"),
+            RelativeDate::Today => write!(f, "TODAY"),
+            RelativeDate::Tomorrow => write!(f, "TOMORROW"),
+            RelativeDate::LastWeek => write!(f, "LAST_WEEK"),
+            RelativeDate::ThisWeek => write!(f, "THIS_WEEK"),
+            RelativeDate::NextWeek => write!(f, "NEXT_WEEK"),
+            RelativeDate::LastMonth => write!(f, "LAST_MONTH"),
+            RelativeDate::ThisMonth => write!(f, "THIS_MONTH"),
+            RelativeDate::NextMonth => write!(f, "NEXT_MONTH"),
+            RelativeDate::Last90Days => write!(f, "LAST_90_DAYS"),
+            RelativeDate::Next90Days => write!(f, "NEXT_90_DAYS"),
+            RelativeDate::LastNDays(n) => write!(f, "LAST_N_DAYS:{}", n),
+            RelativeDate::NextNDays(n) => write!(f, "NEXT_N_DAYS:{}", n),
+            RelativeDate::NDaysAgo(n) => write!(f, "N_DAYS_AGO:{}", n),
+            RelativeDate::LastNWeeks(n) => write!(f, "LAST_N_WEEKS:{}", n),
+            RelativeDate::NextNWeeks(n) => write!(f, "NEXT_N_WEEKS:{}", n),
+            RelativeDate::NWeeksAgo(n) => write!(f, "N_WEEKS_AGO:{}", n),
+            RelativeDate::LastNMonths(n) => write!(f, "LAST_N_MONTHS:{}", n),
+            RelativeDate::NextNMonths(n) => write!(f, "NEXT_N_MONTHS:{}", n),
+            RelativeDate::NMonthsAgo(n) => write!(f, "N_MONTHS_AGO:{}", n),
+            RelativeDate::ThisQuarter => write!(f, "THIS_QUARTER"),
+            RelativeDate::LastQuarter => write!(f, "LAST_QUARTER"),
+            RelativeDate::NextQuarter => write!(f, "NEXT_QUARTER"),
+            RelativeDate::LastNQuarters(n) => write!(f, "LAST_N_QUARTERS:{}", n),
+            RelativeDate::NextNQuarters(n) => write!(f, "NEXT_N_QUARTERS:{}", n),
+            RelativeDate::NQuartersAgo(n) => write!(f, "N_QUARTERS_AGO:{}", n),
+            RelativeDate::ThisYear => write!(f, "THIS_YEAR"),
+            RelativeDate::LastYear => write!(f, "LAST_YEAR"),
+            RelativeDate::NextYear => write!(f, "NEXT_YEAR"),
+            RelativeDate::LastNYears(n) => write!(f, "LAST_N_YEARS:{}", n),
+            RelativeDate::NextNYears(n) => write!(f, "NEXT_N_YEARS:{}", n),
+            RelativeDate::NYearsAgo(n) => write!(f, "N_YEARS_AGO:{}", n),
+            RelativeDate::ThisFiscalQuarter => write!(f, "THIS_FISCAL_QUARTER"),
+            RelativeDate::LastFiscalQuarter => write!(f, "LAST_FISCAL_QUARTER"),
+            RelativeDate::NextFiscalQuarter => write!(f, "NEXT_FISCAL_QUARTER"),
+            RelativeDate::LastNFiscalQuarters(n) => write!(f, "LAST_N_FISCAL_QUARTERS:{}", n),
+            RelativeDate::NextNFiscalQuarters(n) => write!(f, "NEXT_N_FISCAL_QUARTERS:{}", n),
+            RelativeDate::NFiscalQuartersAgo(n) => write!(f, "N_FISCAL_QUARTERS_AGO:{}", n),
+            RelativeDate::ThisFiscalYear => write!(f, "THIS_FISCAL_YEAR"),
+            RelativeDate::LastFiscalYear => write!(f, "LAST_FISCAL_YEAR"),
+            RelativeDate::NextFiscalYear => write!(f, "NEXT_FISCAL_YEAR"),
+            RelativeDate::LastNFiscalYears(n) => write!(f, "LAST_N_FISCAL_YEARS:{}", n),
+            RelativeDate::NextNFiscalYears(n) => write!(f, "NEXT_N_FISCAL_YEARS:{}", n),
+            RelativeDate::NFiscalYearsAgo(n) => write!(f, "N_FISCAL_YEARS_AGO:{}", n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        escape_like, escape_soql, escape_sosl, format_soql_date, format_soql_datetime, RelativeDate,
+    };
+    use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
+
+    #[test]
+    fn escape_soql_literals() {
+        assert_eq!(r"O\'Brien", escape_soql("O'Brien"));
+        assert_eq!(r"C:\\temp", escape_soql(r"C:\temp"));
+        assert_eq!(r#"say \"hi\""#, escape_soql(r#"say "hi""#));
+        assert_eq!(r"a\nb\r\tc", escape_soql("a\nb\r\tc"));
+        assert_eq!(r"\' OR Id != \'", escape_soql("' OR Id != '"));
+        assert_eq!("100%_done", escape_soql("100%_done"));
+    }
+
+    #[test]
+    fn escape_like_wildcards() {
+        assert_eq!(r"100\%\_done", escape_like("100%_done"));
+        assert_eq!(r"O\'Brien\\", escape_like(r"O'Brien\"));
+    }
+
+    #[test]
+    fn escape_sosl_reserved_characters() {
+        assert_eq!(r"Acme \(US\) \- \*", escape_sosl("Acme (US) - *"));
+        assert_eq!(r"O\'Brien \& Sons\?", escape_sosl("O'Brien & Sons?"));
+        assert_eq!(r"\{a\}\\", escape_sosl(r"{a}\"));
+    }
+
+    #[test]
+    fn format_dates() {
+        let date = NaiveDate::from_ymd_opt(2020, 1, 5).unwrap();
+        assert_eq!("2020-01-05", format_soql_date(&date));
+
+        let datetime = Utc.with_ymd_and_hms(2020, 1, 5, 9, 30, 0).unwrap();
+        assert_eq!("2020-01-05T09:30:00Z", format_soql_datetime(&datetime));
+
+        let datetime = FixedOffset::east_opt(9 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2020, 1, 5, 8, 0, 0)
+            .unwrap();
+        assert_eq!("2020-01-04T23:00:00Z", format_soql_datetime(&datetime));
+    }
+
+    #[test]
+    fn relative_dates() {
+        assert_eq!("TODAY", RelativeDate::Today.to_string());
+        assert_eq!("LAST_90_DAYS", RelativeDate::Last90Days.to_string());
+        assert_eq!("LAST_N_DAYS:30", RelativeDate::LastNDays(30).to_string());
+        assert_eq!(
+            "THIS_FISCAL_QUARTER",
+            RelativeDate::ThisFiscalQuarter.to_string()
+        );
+        assert_eq!(
+            "N_FISCAL_YEARS_AGO:2",
+            RelativeDate::NFiscalYearsAgo(2).to_string()
+        );
+    }
+}
@@ -11,6 +11,7 @@ struct Credentials {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 #[serde(rename_all = "PascalCase")]
 pub struct Account {
     #[serde(rename = "attributes")]
@@ -20,6 +21,7 @@ pub struct Account {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 pub struct Attribute {
     pub url: String,
     #[serde(rename = "type")]
@@ -56,7 +58,7 @@ pub async fn create_account(client: &Client, name: &str) -> Result<String> {
 }
 
 pub async fn delete_account(client: &Client, id: &str) -> Result<()> {
-    client.destroy("Account", &id).await?;
+    client.destroy("Account", id).await?;
     Ok(())
 }
 